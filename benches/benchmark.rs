@@ -1,20 +1,175 @@
-use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use criterion::{
+    criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, BenchmarkId, Criterion,
+    Throughput,
+};
 
-pub fn criterion_benchmark(c: &mut Criterion) {
-    // Bitmap which fits in the L1 cache, but whose contents and length are
-    // hidden from the compiler's optimizer
-    static BITMAP: [u8; 32 * 1024] = [42u8; 32 * 1024];
-    fn with_hidden_bitmap_mut(op: impl FnOnce(&mut [u8])) {
-        let mut bitmap = BITMAP;
-        let hidden_bitmap = unsafe {
-            std::slice::from_raw_parts_mut(
-                pessimize::hide(bitmap.as_mut_ptr()),
-                pessimize::hide(bitmap.len()),
-            )
-        };
-        op(hidden_bitmap)
+// Bitmap which fits in the L1 cache, but whose contents and length are
+// hidden from the compiler's optimizer
+static BITMAP: [u8; 32 * 1024] = [42u8; 32 * 1024];
+fn with_hidden_bitmap_mut(op: impl FnOnce(&mut [u8])) {
+    let mut bitmap = BITMAP;
+    op(hide_slice_mut(&mut bitmap))
+}
+
+// Hide the location and length of a slice from the compiler's optimizer
+fn hide_slice_mut<T>(slice: &mut [T]) -> &mut [T] {
+    unsafe {
+        std::slice::from_raw_parts_mut(
+            pessimize::hide(slice.as_mut_ptr()),
+            pessimize::hide(slice.len()),
+        )
     }
+}
+
+// Small xorshift64 pseudo-random number generator, used to generate
+// benchmark inputs which look random but are the same on every run
+fn xorshift64(state: &mut u64) -> u64 {
+    let mut x = *state;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    *state = x;
+    x
+}
+
+// Loop unrolling factor used to amortize benchmark harness and optimization
+// barrier overheads in the benchmarks that probe hidden_indices(). Should not
+// be greater than 4 because x86_64 has 16 registers and we need UNROLL_FACTOR
+// of them for inputs and UNROLL_FACTOR for outputs.
+const UNROLL_FACTOR: usize = 4;
+
+// Indices at which the hidden_constant family of benchmarks probes the bitmap,
+// hidden from the compiler's optimizer
+fn hidden_indices() -> [usize; UNROLL_FACTOR] {
+    [123, 456, 789, 1011].map(pessimize::hide)
+}
+
+// Benchmark operations which test the bits at hidden_indices() in benchmark
+// group `$g`, naming each benchmark after the operation with a `$prefix`, e.g.
+// "test/" so that read and write operations are reported separately.
+// Operations may also come from a submodule of the crate.
+macro_rules! bench_check_hidden_constant {
+    ($g:ident, $prefix:literal: $($op:ident),*) => {
+        bench_check_hidden_constant!(@bench $g, $(
+            concat!($prefix, stringify!($op)) => bit_test::$op
+        ),*)
+    };
+    ($g:ident, $prefix:literal: $module:ident => $($op:ident),*) => {
+        bench_check_hidden_constant!(@bench $g, $(
+            concat!($prefix, stringify!($module), "::", stringify!($op))
+                => bit_test::$module::$op
+        ),*)
+    };
+    (@bench $g:ident, $($name:expr => $op:path),*) => {
+        with_hidden_bitmap_mut(|bitmap| {
+            $(
+                $g.bench_function($name, |b| {
+                    b.iter(|| {
+                        let [i1, i2, i3, i4] = hidden_indices();
+                        let [o1, o2, o3, o4] = [
+                            $op(bitmap, i1),
+                            $op(bitmap, i2),
+                            $op(bitmap, i3),
+                            $op(bitmap, i4),
+                        ];
+                        pessimize::consume(o1);
+                        pessimize::consume(o2);
+                        pessimize::consume(o3);
+                        pessimize::consume(o4);
+                    })
+                });
+            )*
+        });
+    };
+}
+
+// Like bench_check_hidden_constant, but for operations which modify the bits
+macro_rules! bench_change_hidden_constant {
+    ($g:ident, $prefix:literal: $($op:ident),*) => {
+        bench_change_hidden_constant!(@bench $g, $(
+            concat!($prefix, stringify!($op)) => bit_test::$op
+        ),*)
+    };
+    ($g:ident, $prefix:literal: $module:ident => $($op:ident),*) => {
+        bench_change_hidden_constant!(@bench $g, $(
+            concat!($prefix, stringify!($module), "::", stringify!($op))
+                => bit_test::$module::$op
+        ),*)
+    };
+    (@bench $g:ident, $($name:expr => $op:path),*) => {
+        with_hidden_bitmap_mut(|bitmap| {
+            $(
+                $g.bench_function($name, |b| {
+                    b.iter(|| {
+                        let [i1, i2, i3, i4] = hidden_indices();
+                        $op(bitmap, i1);
+                        $op(bitmap, i2);
+                        $op(bitmap, i3);
+                        $op(bitmap, i4);
+                        pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                    })
+                });
+            )*
+        });
+    };
+}
+
+// Benchmark operations which test every bit of `$bitmap` in order, in
+// benchmark group `$g`, with `$id` turning the operation's name into a
+// benchmark ID
+//
+// Here we can use 8-way loop unrolling because the input pattern is simple
+// and not forced to stay resident in registers.
+macro_rules! bench_check_linear_all {
+    ($g:ident, $bitmap:ident, $id:expr; $($op:ident),*) => {
+        $(
+            $g.bench_function($id(stringify!($op)), |b| {
+                b.iter(|| {
+                    for byte in 0..$bitmap.len() {
+                        let first_bit = byte * 8;
+                        let [o1, o2, o3, o4, o5, o6, o7, o8] = [
+                            bit_test::$op($bitmap, first_bit),
+                            bit_test::$op($bitmap, first_bit + 1),
+                            bit_test::$op($bitmap, first_bit + 2),
+                            bit_test::$op($bitmap, first_bit + 3),
+                            bit_test::$op($bitmap, first_bit + 4),
+                            bit_test::$op($bitmap, first_bit + 5),
+                            bit_test::$op($bitmap, first_bit + 6),
+                            bit_test::$op($bitmap, first_bit + 7),
+                        ];
+                        pessimize::consume(o1);
+                        pessimize::consume(o2);
+                        pessimize::consume(o3);
+                        pessimize::consume(o4);
+                        pessimize::consume(o5);
+                        pessimize::consume(o6);
+                        pessimize::consume(o7);
+                        pessimize::consume(o8);
+                    }
+                })
+            });
+        )*
+    };
+}
+
+// Benchmark operations which test the bits of `$bitmap` at each of the
+// `$indices`, in benchmark group `$g`, with `$id` turning the operation's name
+// into a benchmark ID
+macro_rules! bench_check_random_access {
+    ($g:ident, $bitmap:ident, $indices:ident, $id:expr; $($op:ident),*) => {
+        $(
+            $g.bench_function($id(stringify!($op)), |b| {
+                b.iter(|| {
+                    for &idx in $indices.iter() {
+                        pessimize::consume(bit_test::$op($bitmap, idx));
+                    }
+                })
+            });
+        )*
+    };
+}
 
+pub fn criterion_benchmark(c: &mut Criterion) {
     // Query the bitmap at the same hidden locations
     //
     // In this benchmark, the optimizer knows that we're accessing the same
@@ -26,58 +181,59 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     // of the index at which the bitmap is probed is too complex for the
     // optimizer to make sense of.
     {
-        // Loop unrolling factor used to amortize benchmark harness and
-        // optimization barrier overheads. Should not be greater than 4 because
-        // x86_64 has 16 registers and we need UNROLL_FACTOR of them for inputs
-        // and UNROLL_FACTOR for outputs.
-        const UNROLL_FACTOR: usize = 4;
         let mut g = c.benchmark_group("hidden_constant");
         g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
-        let indices = [123, 456, 789, 1011];
-        let hidden_indices = || {
-            let [i1, i2, i3, i4] = indices;
-            [
-                pessimize::hide(i1),
-                pessimize::hide(i2),
-                pessimize::hide(i3),
-                pessimize::hide(i4),
-            ]
-        };
-        macro_rules! bench_check_hidden_constant {
-            ($($op:ident),*) => {
-                with_hidden_bitmap_mut(|bitmap| {
-                    $(
-                        g.bench_function(stringify!($op), |b| {
-                            b.iter(|| {
-                                let [i1, i2, i3, i4] = hidden_indices();
-                                let [o1, o2, o3, o4] = [
-                                    bit_test::$op(bitmap, i1),
-                                    bit_test::$op(bitmap, i2),
-                                    bit_test::$op(bitmap, i3),
-                                    bit_test::$op(bitmap, i4),
-                                ];
-                                pessimize::consume(o1);
-                                pessimize::consume(o2);
-                                pessimize::consume(o3);
-                                pessimize::consume(o4);
-                            })
-                        });
-                    )*
-                });
-            };
-        }
-        bench_check_hidden_constant!(bit_test_naive, bit_test_const_table, bit_test_static_table);
-        macro_rules! bench_change_hidden_constant {
-            ($($op:ident),*) => {
+        bench_check_hidden_constant!(
+            g, "test/": bit_test_naive,
+            bit_test_const_table,
+            bit_test_static_table,
+            bit_test_boxed_table
+        );
+        bench_check_hidden_constant!(g, "test/": noinline => bit_test_naive, bit_test_const_table);
+        bench_change_hidden_constant!(
+            g, "set/": bit_set_naive,
+            bit_set_const_table,
+            bit_set_static_table
+        );
+        bench_change_hidden_constant!(g, "set/": noinline => bit_set_naive, bit_set_const_table);
+        bench_change_hidden_constant!(
+            g, "clear/": bit_clear_naive,
+            bit_clear_const_table,
+            bit_clear_static_table
+        );
+        bench_change_hidden_constant!(
+            g, "clear/": noinline => bit_clear_naive,
+            bit_clear_const_table
+        );
+        bench_change_hidden_constant!(
+            g, "toggle/": bit_toggle_naive,
+            bit_toggle_const_table,
+            bit_toggle_static_table
+        );
+    }
+
+    // Like hidden_constant, but tests each bit and only sets it if it was
+    // clear, as in a check-and-update loop, where the write depends on the
+    // result of the read
+    //
+    // The bits remain set after the first iteration, so this measures the
+    // common case of such loops where most bits are already up to date. Compare
+    // with the fused test-and-set operations of the test_and_set group, which
+    // always write.
+    {
+        let mut g = c.benchmark_group("check_and_update");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        macro_rules! bench_check_and_update {
+            ($($test:ident, $set:ident);*) => {
                 with_hidden_bitmap_mut(|bitmap| {
                     $(
-                        g.bench_function(stringify!($op), |b| {
+                        g.bench_function(concat!(stringify!($test), "+", stringify!($set)), |b| {
                             b.iter(|| {
-                                let [i1, i2, i3, i4] = hidden_indices();
-                                bit_test::$op(bitmap, i1);
-                                bit_test::$op(bitmap, i2);
-                                bit_test::$op(bitmap, i3);
-                                bit_test::$op(bitmap, i4);
+                                for idx in hidden_indices() {
+                                    if !bit_test::$test(bitmap, idx) {
+                                        bit_test::$set(bitmap, idx);
+                                    }
+                                }
                                 pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
                             })
                         });
@@ -85,13 +241,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 });
             };
         }
-        bench_change_hidden_constant!(
-            bit_set_naive,
-            bit_set_const_table,
-            bit_set_static_table,
-            bit_clear_naive,
-            bit_clear_const_table,
-            bit_clear_static_table
+        bench_check_and_update!(
+            bit_test_naive, bit_set_naive;
+            bit_test_const_table, bit_set_const_table;
+            bit_test_static_table, bit_set_static_table
         );
     }
 
@@ -106,7 +259,17 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     {
         let mut g = c.benchmark_group("linear_all");
         g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
-        macro_rules! bench_check_linear_all {
+        with_hidden_bitmap_mut(|bitmap| {
+            bench_check_linear_all!(
+                g,
+                bitmap,
+                |op| op;
+                bit_test_naive,
+                bit_test_const_table,
+                bit_test_static_table
+            );
+        });
+        macro_rules! bench_change_linear_all {
             ($($op:ident),*) => {
                 with_hidden_bitmap_mut(|bitmap| {
                     $(
@@ -117,6 +280,41 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                                     // Here we can use 8-way loop unrolling because the input
                                     // pattern is simple and not forced to stay resident in
                                     // registers.
+                                    bit_test::$op(bitmap, first_bit);
+                                    bit_test::$op(bitmap, first_bit + 1);
+                                    bit_test::$op(bitmap, first_bit + 2);
+                                    bit_test::$op(bitmap, first_bit + 3);
+                                    bit_test::$op(bitmap, first_bit + 4);
+                                    bit_test::$op(bitmap, first_bit + 5);
+                                    bit_test::$op(bitmap, first_bit + 6);
+                                    bit_test::$op(bitmap, first_bit + 7);
+                                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                }
+                            })
+                        });
+                    )*
+                });
+            };
+        }
+        bench_change_linear_all!(
+            bit_set_naive,
+            bit_set_const_table,
+            bit_set_static_table,
+            bit_clear_naive,
+            bit_clear_const_table,
+            bit_clear_static_table,
+            bit_toggle_naive,
+            bit_toggle_const_table,
+            bit_toggle_static_table
+        );
+        macro_rules! bench_test_and_change_linear_all {
+            ($($op:ident),*) => {
+                with_hidden_bitmap_mut(|bitmap| {
+                    $(
+                        g.bench_function(stringify!($op), |b| {
+                            b.iter(|| {
+                                for byte in 0..bitmap.len() {
+                                    let first_bit = byte * 8;
                                     let [o1, o2, o3, o4, o5, o6, o7, o8] = [
                                         bit_test::$op(bitmap, first_bit),
                                         bit_test::$op(bitmap, first_bit + 1),
@@ -135,6 +333,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                                     pessimize::consume(o6);
                                     pessimize::consume(o7);
                                     pessimize::consume(o8);
+                                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
                                 }
                             })
                         });
@@ -142,88 +341,346 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 });
             };
         }
-        bench_check_linear_all!(bit_test_naive, bit_test_const_table, bit_test_static_table);
-        macro_rules! bench_change_linear_all {
+        bench_test_and_change_linear_all!(
+            bit_test_and_clear_naive,
+            bit_test_and_clear_const_table,
+            bit_test_and_clear_static_table
+        );
+    }
+
+    // Like linear_all, but only accesses one bit every STRIDE bits, so that the
+    // change operations do at least require some binary arithmetic
+    //
+    // Each stride is a separate monomorphization, so that the optimizer sees a
+    // constant stride as it would in a hand-written loop. Since fewer bits are
+    // accessed as the stride grows, throughput is measured in accessed bits.
+    // As in linear_all, 8 bits are tested before any result is consumed, so
+    // that STRIDE=1 can be compared with linear_all.
+    {
+        fn bench_stride<const STRIDE: usize>(g: &mut BenchmarkGroup<'_, WallTime>) {
+            const { assert!(STRIDE <= 8 && 8usize.is_multiple_of(STRIDE)) };
+            g.throughput(Throughput::Elements((BITMAP.len() * 8 / STRIDE) as u64));
+            macro_rules! bench_check_linear_strided {
+                ($($op:ident),*) => {
+                    with_hidden_bitmap_mut(|bitmap| {
+                        $(
+                            g.bench_function(BenchmarkId::new(stringify!($op), STRIDE), |b| {
+                                b.iter(|| {
+                                    for first_bit in (0..bitmap.len() * 8).step_by(8 * STRIDE) {
+                                        let outputs: [bool; 8] = std::array::from_fn(|i| {
+                                            bit_test::$op(bitmap, first_bit + i * STRIDE)
+                                        });
+                                        for output in outputs {
+                                            pessimize::consume(output);
+                                        }
+                                    }
+                                })
+                            });
+                        )*
+                    });
+                };
+            }
+            bench_check_linear_strided!(
+                bit_test_naive,
+                bit_test_const_table,
+                bit_test_static_table
+            );
+            macro_rules! bench_change_linear_strided {
+                ($($op:ident),*) => {
+                    with_hidden_bitmap_mut(|bitmap| {
+                        $(
+                            g.bench_function(BenchmarkId::new(stringify!($op), STRIDE), |b| {
+                                b.iter(|| {
+                                    for first_bit in (0..bitmap.len() * 8).step_by(8) {
+                                        for idx in (first_bit..first_bit + 8).step_by(STRIDE) {
+                                            bit_test::$op(bitmap, idx);
+                                        }
+                                        pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                    }
+                                })
+                            });
+                        )*
+                    });
+                };
+            }
+            bench_change_linear_strided!(
+                bit_set_naive,
+                bit_set_const_table,
+                bit_set_static_table,
+                bit_clear_naive,
+                bit_clear_const_table,
+                bit_clear_static_table,
+                bit_toggle_naive,
+                bit_toggle_const_table,
+                bit_toggle_static_table
+            );
+        }
+        let mut g = c.benchmark_group("linear_strided");
+        bench_stride::<1>(&mut g);
+        bench_stride::<2>(&mut g);
+        bench_stride::<4>(&mut g);
+        bench_stride::<8>(&mut g);
+    }
+
+    // Like linear_all, but with various degrees of manual loop unrolling, to
+    // check the assumption that more unrolling exposes more instruction-level
+    // parallelism, until register pressure gets in the way
+    //
+    // The results of each unrolled iteration are all computed before any of
+    // them is consumed, as in the hand-unrolled benchmarks above.
+    {
+        fn bench_unroll<const UNROLL: usize>(g: &mut BenchmarkGroup<'_, WallTime>) {
+            macro_rules! bench_check_unroll_factor {
+                ($($op:ident),*) => {
+                    with_hidden_bitmap_mut(|bitmap| {
+                        $(
+                            g.bench_function(BenchmarkId::new(stringify!($op), UNROLL), |b| {
+                                b.iter(|| {
+                                    for first_bit in (0..bitmap.len() * 8).step_by(UNROLL) {
+                                        let outputs: [bool; UNROLL] = std::array::from_fn(|i| {
+                                            bit_test::$op(bitmap, first_bit + i)
+                                        });
+                                        for output in outputs {
+                                            pessimize::consume(output);
+                                        }
+                                    }
+                                })
+                            });
+                        )*
+                    });
+                };
+            }
+            bench_check_unroll_factor!(bit_test_naive, bit_test_const_table);
+        }
+        let mut g = c.benchmark_group("unroll_factor");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        bench_unroll::<1>(&mut g);
+        bench_unroll::<2>(&mut g);
+        bench_unroll::<4>(&mut g);
+        bench_unroll::<8>(&mut g);
+        bench_unroll::<16>(&mut g);
+    }
+
+    // Like hidden_constant, but for fused read-modify-write operations that
+    // return the previous state of the bit, including the allocator-style
+    // try_set and try_clear, compared against a bare set
+    {
+        let mut g = c.benchmark_group("test_and_set");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        macro_rules! bench_test_and_change_hidden_constant {
             ($($op:ident),*) => {
                 with_hidden_bitmap_mut(|bitmap| {
                     $(
                         g.bench_function(stringify!($op), |b| {
                             b.iter(|| {
-                                for byte in 0..bitmap.len() {
-                                    let first_bit = byte * 8;
-                                    // Here we can use 8-way loop unrolling because the input
-                                    // pattern is simple and not forced to stay resident in
-                                    // registers.
-                                    bit_test::$op(bitmap, first_bit);
-                                    bit_test::$op(bitmap, first_bit + 1);
-                                    bit_test::$op(bitmap, first_bit + 2);
-                                    bit_test::$op(bitmap, first_bit + 3);
-                                    bit_test::$op(bitmap, first_bit + 4);
-                                    bit_test::$op(bitmap, first_bit + 5);
-                                    bit_test::$op(bitmap, first_bit + 6);
-                                    bit_test::$op(bitmap, first_bit + 7);
-                                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
-                                }
+                                let [i1, i2, i3, i4] = hidden_indices();
+                                let [o1, o2, o3, o4] = [
+                                    bit_test::$op(bitmap, i1),
+                                    bit_test::$op(bitmap, i2),
+                                    bit_test::$op(bitmap, i3),
+                                    bit_test::$op(bitmap, i4),
+                                ];
+                                pessimize::consume(o1);
+                                pessimize::consume(o2);
+                                pessimize::consume(o3);
+                                pessimize::consume(o4);
+                                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
                             })
                         });
                     )*
                 });
             };
         }
-        bench_change_linear_all!(
-            bit_set_naive,
+        bench_test_and_change_hidden_constant!(
+            bit_test_and_set_naive,
+            bit_test_and_set_const_table,
+            bit_test_and_set_static_table,
+            try_set,
+            try_clear
+        );
+        bench_change_hidden_constant!(
+            g, "": bit_set_naive,
             bit_set_const_table,
-            bit_set_static_table,
-            bit_clear_naive,
-            bit_clear_const_table,
-            bit_clear_static_table
+            bit_set_static_table
         );
     }
 
-    // Like linear_all, but uses a strided pattern so that the change operations
-    // do at least require some binary arithmetic
+    // Like hidden_constant, but writes a hidden boolean value into each bit
+    //
+    // Since the optimizer doesn't know which value is being written, it must
+    // either emit a data-dependent branch or a branchless masking sequence.
     {
-        let mut g = c.benchmark_group("linear_strided");
-        g.throughput(Throughput::Elements((BITMAP.len() * 4) as u64));
-        macro_rules! bench_check_linear_strided {
+        let mut g = c.benchmark_group("assign");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        let indices = [123, 456, 789, 1011];
+        let values = [true, false, false, true];
+        let hidden_inputs = || {
+            let [i1, i2, i3, i4] = indices;
+            let [v1, v2, v3, v4] = values;
+            [
+                (pessimize::hide(i1), pessimize::hide(v1)),
+                (pessimize::hide(i2), pessimize::hide(v2)),
+                (pessimize::hide(i3), pessimize::hide(v3)),
+                (pessimize::hide(i4), pessimize::hide(v4)),
+            ]
+        };
+        macro_rules! bench_assign_hidden_constant {
             ($($op:ident),*) => {
                 with_hidden_bitmap_mut(|bitmap| {
                     $(
                         g.bench_function(stringify!($op), |b| {
                             b.iter(|| {
-                                for byte in 0..bitmap.len() {
-                                    let first_bit = byte * 8;
-                                    let [o1, o2, o3, o4] = [
-                                        bit_test::$op(bitmap, first_bit),
-                                        bit_test::$op(bitmap, first_bit + 2),
-                                        bit_test::$op(bitmap, first_bit + 4),
-                                        bit_test::$op(bitmap, first_bit + 6),
-                                    ];
-                                    pessimize::consume(o1);
-                                    pessimize::consume(o2);
-                                    pessimize::consume(o3);
-                                    pessimize::consume(o4);
-                                }
+                                let [(i1, v1), (i2, v2), (i3, v3), (i4, v4)] = hidden_inputs();
+                                bit_test::$op(bitmap, i1, v1);
+                                bit_test::$op(bitmap, i2, v2);
+                                bit_test::$op(bitmap, i3, v3);
+                                bit_test::$op(bitmap, i4, v4);
+                                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
                             })
                         });
                     )*
                 });
             };
         }
-        bench_check_linear_strided!(bit_test_naive, bit_test_const_table, bit_test_static_table);
-        macro_rules! bench_change_linear_strided {
+        bench_assign_hidden_constant!(
+            bit_assign_branching,
+            bit_assign_naive,
+            bit_assign_const_table,
+            bit_assign_static_table
+        );
+    }
+
+    // Like hidden_constant, but compares explicitly checked accessors against
+    // the naive ones which rely on slice indexing to panic on invalid indices
+    {
+        let mut g = c.benchmark_group("checked");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        with_hidden_bitmap_mut(|bitmap| {
+            g.bench_function("bit_test_naive", |b| {
+                b.iter(|| {
+                    let [i1, i2, i3, i4] = hidden_indices();
+                    let [o1, o2, o3, o4] = [
+                        bit_test::bit_test_naive(bitmap, i1),
+                        bit_test::bit_test_naive(bitmap, i2),
+                        bit_test::bit_test_naive(bitmap, i3),
+                        bit_test::bit_test_naive(bitmap, i4),
+                    ];
+                    pessimize::consume(o1);
+                    pessimize::consume(o2);
+                    pessimize::consume(o3);
+                    pessimize::consume(o4);
+                })
+            });
+            g.bench_function("bit_test_checked", |b| {
+                b.iter(|| {
+                    let [i1, i2, i3, i4] = hidden_indices();
+                    let [o1, o2, o3, o4] = [
+                        bit_test::bit_test_checked(bitmap, i1),
+                        bit_test::bit_test_checked(bitmap, i2),
+                        bit_test::bit_test_checked(bitmap, i3),
+                        bit_test::bit_test_checked(bitmap, i4),
+                    ];
+                    pessimize::consume(o1.unwrap_or(false));
+                    pessimize::consume(o2.unwrap_or(false));
+                    pessimize::consume(o3.unwrap_or(false));
+                    pessimize::consume(o4.unwrap_or(false));
+                })
+            });
+        });
+        bench_change_hidden_constant!(g, "": bit_set_naive, bit_clear_naive);
+        macro_rules! bench_change_checked_hidden_constant {
             ($($op:ident),*) => {
                 with_hidden_bitmap_mut(|bitmap| {
                     $(
                         g.bench_function(stringify!($op), |b| {
                             b.iter(|| {
-                                for byte in 0..bitmap.len() {
-                                    let first_bit = byte * 8;
-                                    bit_test::$op(bitmap, first_bit);
-                                    bit_test::$op(bitmap, first_bit + 2);
-                                    bit_test::$op(bitmap, first_bit + 4);
-                                    bit_test::$op(bitmap, first_bit + 6);
-                                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                let [i1, i2, i3, i4] = hidden_indices();
+                                let [o1, o2, o3, o4] = [
+                                    bit_test::$op(bitmap, i1),
+                                    bit_test::$op(bitmap, i2),
+                                    bit_test::$op(bitmap, i3),
+                                    bit_test::$op(bitmap, i4),
+                                ];
+                                pessimize::consume(o1.is_ok());
+                                pessimize::consume(o2.is_ok());
+                                pessimize::consume(o3.is_ok());
+                                pessimize::consume(o4.is_ok());
+                                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                            })
+                        });
+                    )*
+                });
+            };
+        }
+        bench_change_checked_hidden_constant!(bit_set_checked, bit_clear_checked);
+    }
+
+    // Bounds checking tax of bit_test in each of the access patterns above,
+    // comparing the naive version (checked by slice indexing) against the
+    // explicitly checked and the unchecked ones
+    {
+        let mut g = c.benchmark_group("bounds_check_cost");
+        macro_rules! bench_bounds_check_cost {
+            ($($name:literal => |$bitmap:ident, $idx:ident| $test:expr),*) => {
+                with_hidden_bitmap_mut(|bitmap| {
+                    let bitmap: &[u8] = bitmap;
+                    $(
+                        let test = |$bitmap: &[u8], $idx: usize| -> bool { $test };
+                        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+                        g.bench_function(concat!("hidden_constant/", $name), |b| {
+                            b.iter(|| {
+                                let [i1, i2, i3, i4] = hidden_indices();
+                                let [o1, o2, o3, o4] = [
+                                    test(bitmap, i1),
+                                    test(bitmap, i2),
+                                    test(bitmap, i3),
+                                    test(bitmap, i4),
+                                ];
+                                pessimize::consume(o1);
+                                pessimize::consume(o2);
+                                pessimize::consume(o3);
+                                pessimize::consume(o4);
+                            })
+                        });
+                        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+                        g.bench_function(concat!("linear_all/", $name), |b| {
+                            b.iter(|| {
+                                for first_bit in (0..bitmap.len() * 8).step_by(8) {
+                                    let [o1, o2, o3, o4, o5, o6, o7, o8] = [
+                                        test(bitmap, first_bit),
+                                        test(bitmap, first_bit + 1),
+                                        test(bitmap, first_bit + 2),
+                                        test(bitmap, first_bit + 3),
+                                        test(bitmap, first_bit + 4),
+                                        test(bitmap, first_bit + 5),
+                                        test(bitmap, first_bit + 6),
+                                        test(bitmap, first_bit + 7),
+                                    ];
+                                    pessimize::consume(o1);
+                                    pessimize::consume(o2);
+                                    pessimize::consume(o3);
+                                    pessimize::consume(o4);
+                                    pessimize::consume(o5);
+                                    pessimize::consume(o6);
+                                    pessimize::consume(o7);
+                                    pessimize::consume(o8);
+                                }
+                            })
+                        });
+                        g.throughput(Throughput::Elements((BITMAP.len() * 4) as u64));
+                        g.bench_function(concat!("linear_strided/", $name), |b| {
+                            b.iter(|| {
+                                for first_bit in (0..bitmap.len() * 8).step_by(8) {
+                                    let [o1, o2, o3, o4] = [
+                                        test(bitmap, first_bit),
+                                        test(bitmap, first_bit + 2),
+                                        test(bitmap, first_bit + 4),
+                                        test(bitmap, first_bit + 6),
+                                    ];
+                                    pessimize::consume(o1);
+                                    pessimize::consume(o2);
+                                    pessimize::consume(o3);
+                                    pessimize::consume(o4);
                                 }
                             })
                         });
@@ -231,15 +688,1934 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 });
             };
         }
-        bench_change_linear_strided!(
-            bit_set_naive,
-            bit_set_const_table,
-            bit_set_static_table,
-            bit_clear_naive,
-            bit_clear_const_table,
-            bit_clear_static_table
+        bench_bounds_check_cost!(
+            "naive" => |bitmap, idx| bit_test::bit_test_naive(bitmap, idx),
+            "checked" => |bitmap, idx| bit_test::bit_test_checked(bitmap, idx).unwrap_or(false),
+            // SAFETY: All benchmarked indices are within the bitmap
+            "unchecked" => |bitmap, idx| unsafe { bit_test::bit_test_unchecked(bitmap, idx) }
         );
     }
+
+    // Word-granular counterparts of the hidden_constant and linear_all
+    // benchmarks, operating on u64 words (u64_backed) or on words of the
+    // platform's native width (usize_backed) instead of bytes, so that 32-bit
+    // and 64-bit targets both use their natural word size
+    {
+        macro_rules! bench_word_backed {
+            ($group:literal, $word:ty: $test:ident, $($change:ident),*) => {{
+                // Same bitmap as BITMAP, but stored as properly aligned words.
+                // Words are built in little-endian order so that bit indices
+                // map to the same bits as in the byte-granular representation.
+                const WORD_BYTES: usize = std::mem::size_of::<$word>();
+                let mut words = [0 as $word; BITMAP.len() / WORD_BYTES];
+                for (word, bytes) in words.iter_mut().zip(BITMAP.chunks_exact(WORD_BYTES)) {
+                    *word = <$word>::from_le_bytes(bytes.try_into().unwrap());
+                }
+                let words = hide_slice_mut(&mut words);
+                let mut g = c.benchmark_group($group);
+
+                g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+                g.bench_function(concat!("hidden_constant/", stringify!($test)), |b| {
+                    b.iter(|| {
+                        let [i1, i2, i3, i4] = hidden_indices();
+                        let [o1, o2, o3, o4] = [
+                            bit_test::$test(words, i1),
+                            bit_test::$test(words, i2),
+                            bit_test::$test(words, i3),
+                            bit_test::$test(words, i4),
+                        ];
+                        pessimize::consume(o1);
+                        pessimize::consume(o2);
+                        pessimize::consume(o3);
+                        pessimize::consume(o4);
+                    })
+                });
+                $(
+                    g.bench_function(concat!("hidden_constant/", stringify!($change)), |b| {
+                        b.iter(|| {
+                            let [i1, i2, i3, i4] = hidden_indices();
+                            bit_test::$change(words, i1);
+                            bit_test::$change(words, i2);
+                            bit_test::$change(words, i3);
+                            bit_test::$change(words, i4);
+                            pessimize::assume_accessed(&mut words.as_mut_ptr());
+                        })
+                    });
+                )*
+
+                g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+                g.bench_function(concat!("linear_all/", stringify!($test)), |b| {
+                    b.iter(|| {
+                        for byte in 0..words.len() * WORD_BYTES {
+                            let first_bit = byte * 8;
+                            let [o1, o2, o3, o4, o5, o6, o7, o8] = [
+                                bit_test::$test(words, first_bit),
+                                bit_test::$test(words, first_bit + 1),
+                                bit_test::$test(words, first_bit + 2),
+                                bit_test::$test(words, first_bit + 3),
+                                bit_test::$test(words, first_bit + 4),
+                                bit_test::$test(words, first_bit + 5),
+                                bit_test::$test(words, first_bit + 6),
+                                bit_test::$test(words, first_bit + 7),
+                            ];
+                            pessimize::consume(o1);
+                            pessimize::consume(o2);
+                            pessimize::consume(o3);
+                            pessimize::consume(o4);
+                            pessimize::consume(o5);
+                            pessimize::consume(o6);
+                            pessimize::consume(o7);
+                            pessimize::consume(o8);
+                        }
+                    })
+                });
+                $(
+                    g.bench_function(concat!("linear_all/", stringify!($change)), |b| {
+                        b.iter(|| {
+                            for byte in 0..words.len() * WORD_BYTES {
+                                let first_bit = byte * 8;
+                                bit_test::$change(words, first_bit);
+                                bit_test::$change(words, first_bit + 1);
+                                bit_test::$change(words, first_bit + 2);
+                                bit_test::$change(words, first_bit + 3);
+                                bit_test::$change(words, first_bit + 4);
+                                bit_test::$change(words, first_bit + 5);
+                                bit_test::$change(words, first_bit + 6);
+                                bit_test::$change(words, first_bit + 7);
+                                pessimize::assume_accessed(&mut words.as_mut_ptr());
+                            }
+                        })
+                    });
+                )*
+            }};
+        }
+        bench_word_backed!("u64_backed", u64: bit_test_u64, bit_set_u64, bit_clear_u64);
+        bench_word_backed!("usize_backed", usize: bit_test_word, bit_set_word, bit_clear_word);
+    }
+
+    // Count the number of set bits in the whole bitmap
+    //
+    // The bitmap is marked as possibly modified on each iteration so that the
+    // optimizer cannot hoist the computation out of the benchmark loop.
+    {
+        let mut g = c.benchmark_group("popcount");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        macro_rules! bench_popcount {
+            ($($op:ident),*) => {
+                with_hidden_bitmap_mut(|bitmap| {
+                    $(
+                        g.bench_function(stringify!($op), |b| {
+                            b.iter(|| {
+                                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                pessimize::consume(bit_test::$op(bitmap));
+                            })
+                        });
+                    )*
+                });
+            };
+        }
+        bench_popcount!(count_ones, count_ones_table, count_ones_u64, count_zeros);
+        #[cfg(feature = "portable_simd")]
+        bench_popcount!(count_ones_simd);
+        #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+        bench_popcount!(count_ones_neon);
+    }
+
+    // Count set bits per bucket, for bucket sizes which are and are not a
+    // whole number of bytes
+    #[cfg(feature = "std")]
+    {
+        let mut g = c.benchmark_group("bucket_popcounts");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        with_hidden_bitmap_mut(|bitmap| {
+            for bucket_bits in [12, 64, 512, 4096] {
+                g.bench_function(BenchmarkId::from_parameter(bucket_bits), |b| {
+                    b.iter(|| {
+                        pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                        pessimize::consume(bit_test::bucket_popcounts(
+                            bitmap,
+                            pessimize::hide(bucket_bits),
+                        ));
+                    })
+                });
+            }
+        });
+    }
+
+    // Search for the first set bit in a bitmap where only a single bit is set,
+    // at various positions, or no bit is set at all
+    //
+    // Throughput is measured in bits scanned before the search terminates.
+    {
+        let mut g = c.benchmark_group("find_first_set");
+        let num_bits = BITMAP.len() * 8;
+        for (name, set_bit) in [
+            ("first", Some(0)),
+            ("middle", Some(num_bits / 2)),
+            ("last", Some(num_bits - 1)),
+            ("none", None),
+        ] {
+            let mut bitmap = [0u8; BITMAP.len()];
+            if let Some(idx) = set_bit {
+                bit_test::bit_set_naive(&mut bitmap, idx);
+            }
+            let bitmap = hide_slice_mut(&mut bitmap);
+            g.throughput(Throughput::Elements(
+                set_bit.map_or(num_bits, |idx| idx + 1) as u64,
+            ));
+            macro_rules! bench_find {
+                ($($op:ident),*) => {
+                    $(
+                        g.bench_function(BenchmarkId::new(stringify!($op), name), |b| {
+                            b.iter(|| {
+                                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                pessimize::consume(bit_test::$op(bitmap).unwrap_or(usize::MAX));
+                            })
+                        });
+                    )*
+                };
+            }
+            bench_find!(find_first_set);
+            #[cfg(feature = "portable_simd")]
+            bench_find!(find_first_set_simd);
+        }
+    }
+
+    // Enumerate the set bits of a half-full pseudo-random bitmap, either using
+    // the set_bits iterator or by probing every bit with bit_test
+    //
+    // Throughput is measured in set bits enumerated.
+    {
+        let mut g = c.benchmark_group("set_bits");
+        let mut bitmap = [0u8; BITMAP.len()];
+        let mut rng_state = 0x2545_f491_4f6c_dd1d;
+        for byte in &mut bitmap {
+            *byte = xorshift64(&mut rng_state) as u8;
+        }
+        let bitmap = hide_slice_mut(&mut bitmap);
+        g.throughput(Throughput::Elements(bit_test::count_ones(bitmap) as u64));
+        g.bench_function("set_bits", |b| {
+            b.iter(|| {
+                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                for idx in bit_test::set_bits(bitmap) {
+                    pessimize::consume(idx);
+                }
+            })
+        });
+        g.bench_function("set_bits_rev", |b| {
+            b.iter(|| {
+                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                for idx in bit_test::set_bits(bitmap).rev() {
+                    pessimize::consume(idx);
+                }
+            })
+        });
+        g.bench_function("bit_test_naive", |b| {
+            b.iter(|| {
+                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                for idx in 0..bitmap.len() * 8 {
+                    if bit_test::bit_test_naive(bitmap, idx) {
+                        pessimize::consume(idx);
+                    }
+                }
+            })
+        });
+    }
+
+    // Read the 16-bit field starting at every bit offset of the bitmap, either
+    // with the specialized two-byte window reader or with generic get_bits
+    {
+        let mut g = c.benchmark_group("read_u16");
+        const NUM_OFFSETS: usize = BITMAP.len() * 8 - 15;
+        g.throughput(Throughput::Elements(NUM_OFFSETS as u64));
+        with_hidden_bitmap_mut(|bitmap| {
+            g.bench_function("read_u16_at", |b| {
+                b.iter(|| {
+                    for bit_offset in 0..NUM_OFFSETS {
+                        pessimize::consume(bit_test::read_u16_at(bitmap, bit_offset));
+                    }
+                })
+            });
+            g.bench_function("get_bits", |b| {
+                b.iter(|| {
+                    for bit_offset in 0..NUM_OFFSETS {
+                        pessimize::consume(bit_test::get_bits(bitmap, bit_offset, 16));
+                    }
+                })
+            });
+        });
+    }
+
+    // Convert a bitmap where about 10% of the bits are set to the sparse list
+    // of its set bit indices, and back
+    //
+    // Each index of the sparse representation takes 32 bits, so it is only
+    // smaller than the bitmap below a density of 1/32, i.e. about 3%. At 10%,
+    // the sparse representation is about 3 times larger than the bitmap.
+    #[cfg(feature = "std")]
+    {
+        let mut g = c.benchmark_group("sparse");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        let mut bitmap = [0u8; BITMAP.len()];
+        let mut rng_state = 0x2545_f491_4f6c_dd1d;
+        for idx in 0..BITMAP.len() * 8 {
+            if xorshift64(&mut rng_state).is_multiple_of(10) {
+                bit_test::bit_set_naive(&mut bitmap, idx);
+            }
+        }
+        let mut sparse = bit_test::to_sparse(&bitmap);
+        let bitmap = hide_slice_mut(&mut bitmap);
+        let sparse = hide_slice_mut(&mut sparse);
+        g.bench_function("to_sparse", |b| {
+            b.iter(|| {
+                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                pessimize::consume(bit_test::to_sparse(bitmap));
+            })
+        });
+        g.bench_function("from_sparse", |b| {
+            b.iter(|| {
+                pessimize::assume_accessed(&mut sparse.as_mut_ptr());
+                pessimize::consume(bit_test::from_sparse(sparse, BITMAP.len() * 8));
+            })
+        });
+    }
+
+    // Set, clear or toggle ranges of bits of various lengths, either using the
+    // bulk range operations or by changing each bit of the range individually
+    //
+    // Ranges start at a non-byte-aligned index so that the partial bytes at
+    // the edges of the range must be handled, and their lengths are not always
+    // multiples of 8. Short ranges are dominated by the cost of masking the
+    // partial bytes at the edges of the range, which is amortized by the bulk
+    // processing of the fully covered bytes in the middle as ranges get longer.
+    {
+        let mut g = c.benchmark_group("bulk_vs_per_bit");
+        const START: usize = 3;
+        for (len_name, len) in [
+            ("1", 1),
+            ("7", 7),
+            ("8", 8),
+            ("64", 64),
+            ("512", 512),
+            ("1000", 1000),
+            ("4096", 4096),
+            ("full", BITMAP.len() * 8 - START),
+        ] {
+            g.throughput(Throughput::Elements(len as u64));
+            macro_rules! bench_range_op {
+                ($($op:ident),*) => {
+                    with_hidden_bitmap_mut(|bitmap| {
+                        $(
+                            g.bench_function(BenchmarkId::new(stringify!($op), len_name), |b| {
+                                b.iter(|| {
+                                    let start = pessimize::hide(START);
+                                    bit_test::$op(bitmap, start, start + len);
+                                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                })
+                            });
+                        )*
+                    });
+                };
+            }
+            bench_range_op!(set_range, clear_range, toggle_range);
+            macro_rules! bench_per_bit_op {
+                ($($op:ident),*) => {
+                    with_hidden_bitmap_mut(|bitmap| {
+                        $(
+                            g.bench_function(BenchmarkId::new(stringify!($op), len_name), |b| {
+                                b.iter(|| {
+                                    let start = pessimize::hide(START);
+                                    for idx in start..start + len {
+                                        bit_test::$op(bitmap, idx);
+                                    }
+                                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                })
+                            });
+                        )*
+                    });
+                };
+            }
+            bench_per_bit_op!(bit_set_naive, bit_clear_naive, bit_toggle_naive);
+        }
+    }
+
+    // Check if all or any bits within ranges of various lengths are set, in a
+    // bitmap where all bits are set
+    //
+    // test_range_all must scan the entire range, whereas test_range_any can
+    // stop as soon as it encounters the first set bit.
+    {
+        let mut g = c.benchmark_group("range_predicates");
+        let mut bitmap = [u8::MAX; BITMAP.len()];
+        let bitmap = hide_slice_mut(&mut bitmap);
+        const START: usize = 3;
+        for len in [1, 8, 64, 512, 4096, BITMAP.len() * 8 - START] {
+            g.throughput(Throughput::Elements(len as u64));
+            macro_rules! bench_range_predicate {
+                ($($op:ident),*) => {
+                    $(
+                        g.bench_function(BenchmarkId::new(stringify!($op), len), |b| {
+                            b.iter(|| {
+                                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                let start = pessimize::hide(START);
+                                pessimize::consume(bit_test::$op(bitmap, start, start + len));
+                            })
+                        });
+                    )*
+                };
+            }
+            bench_range_predicate!(test_range_all, test_range_any);
+        }
+    }
+
+    // Fill the whole bitmap through many set_range calls of a given size
+    //
+    // Small ranges result in many narrow read-modify-write stores to the same
+    // cache lines, which the store buffer may or may not coalesce, whereas
+    // large ranges are mostly filled by wide stores. The range size is hidden so
+    // that the optimizer can't merge consecutive calls into a single fill.
+    {
+        let mut g = c.benchmark_group("sequential_fill");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        for chunk_bits in [1, 8, 64, 4096] {
+            with_hidden_bitmap_mut(|bitmap| {
+                g.bench_function(BenchmarkId::new("set_range", chunk_bits), |b| {
+                    b.iter(|| {
+                        let chunk_bits = pessimize::hide(chunk_bits);
+                        for start in (0..bitmap.len() * 8).step_by(chunk_bits) {
+                            bit_test::set_range(bitmap, start, start + chunk_bits);
+                        }
+                        pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                    })
+                });
+            });
+        }
+    }
+
+    // Set or clear every bit of the bitmap
+    //
+    // The manual loop is likely to be turned into a memset by the optimizer,
+    // which is something that this benchmark can confirm.
+    {
+        let mut g = c.benchmark_group("fill");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        for (name, value) in [("set", u8::MAX), ("clear", 0)] {
+            with_hidden_bitmap_mut(|bitmap| {
+                g.bench_function(format!("{name}_slice_fill"), |b| {
+                    b.iter(|| {
+                        bitmap.fill(pessimize::hide(value));
+                        pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                    })
+                });
+                g.bench_function(format!("{name}_scalar_loop"), |b| {
+                    b.iter(|| {
+                        let value = pessimize::hide(value);
+                        for byte in bitmap.iter_mut() {
+                            *byte = value;
+                        }
+                        pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                    })
+                });
+            });
+        }
+        #[cfg(target_arch = "x86_64")]
+        {
+            macro_rules! bench_fill {
+                ($($op:ident),*) => {
+                    with_hidden_bitmap_mut(|bitmap| {
+                        $(
+                            g.bench_function(stringify!($op), |b| {
+                                b.iter(|| {
+                                    bit_test::$op(bitmap);
+                                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                })
+                            });
+                        )*
+                    });
+                };
+            }
+            bench_fill!(set_all_avx2, clear_all_avx2);
+        }
+    }
+
+    // Hammer an atomic bitmap from several threads at once, where each thread
+    // either targets its own cache line of the bitmap or all threads target
+    // the same bit
+    //
+    // Throughput is measured in operations summed across all threads.
+    {
+        use bit_test::atomic;
+        use std::{
+            sync::atomic::{AtomicU8, Ordering},
+            thread,
+            time::Instant,
+        };
+        const NUM_THREADS: usize = 4;
+        let mut g = c.benchmark_group("atomic");
+        g.throughput(Throughput::Elements(NUM_THREADS as u64));
+        let bitmap = (0..BITMAP.len())
+            .map(|_| AtomicU8::new(0))
+            .collect::<Vec<_>>();
+        let bitmap = &bitmap[..];
+        for (pattern, thread_stride) in [("disjoint", 64 * 8), ("overlapping", 0)] {
+            for order in [Ordering::Relaxed, Ordering::SeqCst] {
+                macro_rules! bench_contended {
+                    ($($op:ident),*) => {
+                        $(
+                            g.bench_function(
+                                format!("{}/{pattern}/{order:?}", stringify!($op)),
+                                |b| {
+                                    b.iter_custom(|iters| {
+                                        let start = Instant::now();
+                                        thread::scope(|s| {
+                                            for thread_idx in 0..NUM_THREADS {
+                                                s.spawn(move || {
+                                                    let idx = thread_idx * thread_stride;
+                                                    for _ in 0..iters {
+                                                        let idx = pessimize::hide(idx);
+                                                        pessimize::consume(atomic::$op(bitmap, idx, order));
+                                                    }
+                                                });
+                                            }
+                                        });
+                                        start.elapsed()
+                                    })
+                                },
+                            );
+                        )*
+                    };
+                }
+                bench_contended!(atomic_bit_set, atomic_test_and_set);
+            }
+        }
+    }
+
+    // Like atomic, but each thread sets its own bit, which either lies in the
+    // same byte as the bits of the other threads or in its own cache line
+    //
+    // Even though the threads never touch the same bit, bits which share a
+    // byte (and thus a cache line) are contended, which is why hot bits of
+    // allocation bitmaps should be spread across cache lines. Throughput is
+    // measured in operations per thread.
+    {
+        use bit_test::atomic;
+        use std::{
+            sync::atomic::{AtomicU8, Ordering},
+            thread,
+            time::Instant,
+        };
+        let mut g = c.benchmark_group("false_sharing");
+        g.throughput(Throughput::Elements(1));
+        let bitmap = (0..BITMAP.len())
+            .map(|_| AtomicU8::new(0))
+            .collect::<Vec<_>>();
+        let bitmap = &bitmap[..];
+        for (pattern, thread_stride) in [("same_byte", 1), ("distinct_lines", 64 * 8)] {
+            for num_threads in [1, 2, 4, 8] {
+                g.bench_function(BenchmarkId::new(pattern, num_threads), |b| {
+                    b.iter_custom(|iters| {
+                        let start = Instant::now();
+                        thread::scope(|s| {
+                            for thread_idx in 0..num_threads {
+                                s.spawn(move || {
+                                    let idx = thread_idx * thread_stride;
+                                    for _ in 0..iters {
+                                        let idx = pessimize::hide(idx);
+                                        atomic::atomic_bit_set(bitmap, idx, Ordering::Relaxed);
+                                    }
+                                });
+                            }
+                        });
+                        start.elapsed()
+                    })
+                });
+            }
+        }
+    }
+
+    // Combine the bitmap with a second, pseudo-random bitmap of the same size
+    {
+        let mut g = c.benchmark_group("setalg");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        let mut src = [0u8; BITMAP.len()];
+        let mut rng_state = 0x9e37_79b9_7f4a_7c15;
+        for byte in &mut src {
+            *byte = xorshift64(&mut rng_state) as u8;
+        }
+        let src = hide_slice_mut(&mut src);
+        macro_rules! bench_setalg {
+            ($($op:ident),*) => {
+                with_hidden_bitmap_mut(|dst| {
+                    $(
+                        g.bench_function(stringify!($op), |b| {
+                            b.iter(|| {
+                                pessimize::assume_accessed(&mut src.as_mut_ptr());
+                                bit_test::$op(dst, src);
+                                pessimize::assume_accessed(&mut dst.as_mut_ptr());
+                            })
+                        });
+                    )*
+                });
+            };
+        }
+        bench_setalg!(bitmap_and, bitmap_or, bitmap_xor, bitmap_andnot);
+        #[cfg(target_arch = "x86_64")]
+        bench_setalg!(bitmap_and_avx2, bitmap_or_avx2, bitmap_xor_avx2);
+        #[cfg(feature = "portable_simd")]
+        bench_setalg!(bitmap_and_simd, bitmap_or_simd, bitmap_xor_simd);
+    }
+
+    // Count the bits of the combination of two bitmaps, either in a single
+    // fused pass or by combining the bitmaps in place then counting bits
+    {
+        let mut g = c.benchmark_group("setalg_count");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        let mut src = [0u8; BITMAP.len()];
+        let mut rng_state = 0x9e37_79b9_7f4a_7c15;
+        for byte in &mut src {
+            *byte = xorshift64(&mut rng_state) as u8;
+        }
+        let src = hide_slice_mut(&mut src);
+        macro_rules! bench_fused_count {
+            ($($op:ident),*) => {
+                with_hidden_bitmap_mut(|dst| {
+                    $(
+                        g.bench_function(stringify!($op), |b| {
+                            b.iter(|| {
+                                pessimize::assume_accessed(&mut src.as_mut_ptr());
+                                pessimize::assume_accessed(&mut dst.as_mut_ptr());
+                                pessimize::consume(bit_test::$op(dst, src));
+                            })
+                        });
+                    )*
+                });
+            };
+        }
+        bench_fused_count!(and_count, or_count, xor_count);
+        macro_rules! bench_two_pass_count {
+            ($($op:ident),*) => {
+                with_hidden_bitmap_mut(|dst| {
+                    $(
+                        g.bench_function(concat!(stringify!($op), "+count_ones"), |b| {
+                            b.iter(|| {
+                                pessimize::assume_accessed(&mut src.as_mut_ptr());
+                                bit_test::$op(dst, src);
+                                pessimize::assume_accessed(&mut dst.as_mut_ptr());
+                                pessimize::consume(bit_test::count_ones(dst));
+                            })
+                        });
+                    )*
+                });
+            };
+        }
+        bench_two_pass_count!(bitmap_and, bitmap_or, bitmap_xor);
+    }
+
+    // Like hidden_constant, but for MSB-first bit numbering
+    {
+        let mut g = c.benchmark_group("msb");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        bench_check_hidden_constant!(g, "": bit_test_msb, bit_test_msb_table);
+        bench_change_hidden_constant!(
+            g, "": bit_set_msb,
+            bit_set_msb_table,
+            bit_clear_msb,
+            bit_clear_msb_table
+        );
+    }
+
+    // Probe the bitmap at pseudo-random indices spanning the whole bitmap
+    //
+    // Unlike hidden_constant, which always probes the same few bytes, this
+    // models workloads like hash tables where accesses are scattered across
+    // the bitmap. The indices are generated ahead of time so that the PRNG's
+    // cost is not measured.
+    {
+        const NUM_INDICES: usize = 4096;
+        let mut g = c.benchmark_group("random_access");
+        g.throughput(Throughput::Elements(NUM_INDICES as u64));
+        let mut rng_state = 0xd1b5_4a32_d192_ed03;
+        let mut indices = (0..NUM_INDICES)
+            .map(|_| xorshift64(&mut rng_state) as usize % (BITMAP.len() * 8))
+            .collect::<Vec<_>>();
+        let indices = hide_slice_mut(&mut indices);
+        with_hidden_bitmap_mut(|bitmap| {
+            bench_check_random_access!(
+                g,
+                bitmap,
+                indices,
+                |op| op;
+                bit_test_naive,
+                bit_test_const_table,
+                bit_test_static_table
+            );
+        });
+    }
+
+    // Like linear_all and random_access, but with bitmaps of increasing size
+    // that eventually exceed the L1, L2 and L3 caches
+    //
+    // The bitmaps are allocated on the heap to avoid bloating the binary.
+    {
+        const SIZES: [(&str, usize); 4] = [
+            ("32KiB", 32 * 1024),
+            ("512KiB", 512 * 1024),
+            ("8MiB", 8 * 1024 * 1024),
+            ("64MiB", 64 * 1024 * 1024),
+        ];
+        const NUM_INDICES: usize = 4096;
+        let mut linear = c.benchmark_group("linear_all_by_size");
+        // Scanning the largest bitmaps takes a while, so take fewer samples
+        linear.sample_size(10);
+        for (size_name, size) in SIZES {
+            let mut bitmap = vec![42u8; size];
+            let bitmap = hide_slice_mut(&mut bitmap);
+            linear.throughput(Throughput::Elements((size * 8) as u64));
+            bench_check_linear_all!(
+                linear,
+                bitmap,
+                |op| BenchmarkId::new(op, size_name);
+                bit_test_naive,
+                bit_test_const_table,
+                bit_test_static_table
+            );
+        }
+        linear.finish();
+
+        let mut random = c.benchmark_group("random_access_by_size");
+        random.throughput(Throughput::Elements(NUM_INDICES as u64));
+        for (size_name, size) in SIZES {
+            let mut bitmap = vec![42u8; size];
+            let bitmap = hide_slice_mut(&mut bitmap);
+            let mut rng_state = 0xd1b5_4a32_d192_ed03;
+            let mut indices = (0..NUM_INDICES)
+                .map(|_| xorshift64(&mut rng_state) as usize % (size * 8))
+                .collect::<Vec<_>>();
+            let indices = hide_slice_mut(&mut indices);
+            bench_check_random_access!(
+                random,
+                bitmap,
+                indices,
+                |op| BenchmarkId::new(op, size_name);
+                bit_test_naive,
+                bit_test_const_table,
+                bit_test_static_table
+            );
+        }
+    }
+
+    // Like popcount, but with bitmaps of increasing size, comparing the serial
+    // count with the multi-threaded one
+    //
+    // The parallel count only pays off once the bitmap is large enough to
+    // amortize the cost of distributing the work across threads.
+    {
+        const SIZES: [(&str, usize); 4] = [
+            ("32KiB", 32 * 1024),
+            ("512KiB", 512 * 1024),
+            ("8MiB", 8 * 1024 * 1024),
+            ("64MiB", 64 * 1024 * 1024),
+        ];
+        let mut g = c.benchmark_group("popcount_parallel");
+        for (size_name, size) in SIZES {
+            let mut bitmap = vec![42u8; size];
+            let bitmap = hide_slice_mut(&mut bitmap);
+            g.throughput(Throughput::Elements((size * 8) as u64));
+            macro_rules! bench_popcount {
+                ($($op:ident),*) => {
+                    $(
+                        g.bench_function(BenchmarkId::new(stringify!($op), size_name), |b| {
+                            b.iter(|| {
+                                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                pessimize::consume(bit_test::$op(bitmap));
+                            })
+                        });
+                    )*
+                };
+            }
+            bench_popcount!(count_ones);
+            #[cfg(feature = "rayon")]
+            bench_popcount!(count_ones_parallel);
+        }
+    }
+
+    // Count the set bits of a large bitmap which is stored in a file, either
+    // read into RAM beforehand or memory-mapped
+    //
+    // The fresh mapping is created inside of the timed loop, so it measures
+    // the cost of the page faults that bring the file into the address space
+    // (the file itself should stay in the OS page cache). The mapping which is
+    // reused across iterations should perform like the in-RAM bitmap.
+    #[cfg(feature = "mmap")]
+    {
+        const SIZE: usize = 64 * 1024 * 1024;
+        let path = std::env::temp_dir().join("bit_test_mmap_benchmark.bin");
+        std::fs::write(&path, vec![42u8; SIZE]).unwrap();
+        let mut g = c.benchmark_group("mmap");
+        g.throughput(Throughput::Elements((SIZE * 8) as u64));
+        let mut in_ram = std::fs::read(&path).unwrap();
+        let in_ram = hide_slice_mut(&mut in_ram);
+        g.bench_function("in_ram", |b| {
+            b.iter(|| {
+                pessimize::assume_accessed(&mut in_ram.as_mut_ptr());
+                pessimize::consume(bit_test::count_ones(in_ram));
+            })
+        });
+        let mapped = bit_test::mmap::open_mmap(&path).unwrap();
+        g.bench_function("mapped_once", |b| {
+            b.iter(|| {
+                pessimize::assume_accessed(&mut mapped.as_ptr());
+                pessimize::consume(bit_test::count_ones(&mapped));
+            })
+        });
+        g.bench_function("mapped_each_time", |b| {
+            b.iter(|| {
+                let mapped = bit_test::mmap::open_mmap(&path).unwrap();
+                pessimize::consume(bit_test::count_ones(&mapped));
+            })
+        });
+        drop(mapped);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    // Clear every bit of the bitmap, either with a memset or bit by bit
+    //
+    // Indices are hidden in the bit-by-bit version, otherwise the optimizer
+    // would turn the loop into a memset as in linear_all.
+    {
+        let mut g = c.benchmark_group("reset");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        with_hidden_bitmap_mut(|bitmap| {
+            g.bench_function("reset", |b| {
+                b.iter(|| {
+                    bit_test::reset(bitmap);
+                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                })
+            });
+            g.bench_function("bit_clear_naive", |b| {
+                b.iter(|| {
+                    for idx in 0..bitmap.len() * 8 {
+                        bit_test::bit_clear_naive(bitmap, pessimize::hide(idx));
+                    }
+                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                })
+            });
+        });
+    }
+
+    // Reverse the order of bits within each byte or across the whole bitmap
+    {
+        let mut g = c.benchmark_group("reverse");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        macro_rules! bench_reverse {
+            ($($op:ident),*) => {
+                with_hidden_bitmap_mut(|bitmap| {
+                    $(
+                        g.bench_function(stringify!($op), |b| {
+                            b.iter(|| {
+                                bit_test::$op(bitmap);
+                                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                            })
+                        });
+                    )*
+                });
+            };
+        }
+        bench_reverse!(reverse_byte_bits, reverse_all_bits);
+    }
+
+    // Sweep through all bit patterns of a 16-bit bitmap, in binary counting
+    // order or in Gray code order
+    //
+    // Most increments stop at the first byte, whereas each Gray code step
+    // must compute the parity of the whole bitmap.
+    {
+        let mut g = c.benchmark_group("sweep");
+        g.throughput(Throughput::Elements(1 << 16));
+        macro_rules! bench_sweep {
+            ($($op:ident),*) => {
+                $(
+                    let mut bitmap = [0u8; 2];
+                    let bitmap = hide_slice_mut(&mut bitmap);
+                    g.bench_function(stringify!($op), |b| {
+                        b.iter(|| {
+                            for _ in 0..1 << 16 {
+                                pessimize::consume(bit_test::$op(bitmap));
+                            }
+                            pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                        })
+                    });
+                )*
+            };
+        }
+        bench_sweep!(increment, gray_code_successor);
+    }
+
+    // Flip every bit of the bitmap
+    {
+        let mut g = c.benchmark_group("complement");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        with_hidden_bitmap_mut(|bitmap| {
+            g.bench_function("complement", |b| {
+                b.iter(|| {
+                    bit_test::complement(bitmap);
+                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                })
+            });
+        });
+    }
+
+    // Compare the bitmap with an identical bitmap, or with a bitmap that
+    // differs in its middle byte
+    //
+    // All comparisons stop at the first byte where the answer is known, so
+    // the bitmap is only partially scanned when the answer is false.
+    {
+        let mut g = c.benchmark_group("compare");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        for (name, differing_byte) in [("equal", None), ("differing", Some(BITMAP.len() / 2))] {
+            let mut other = BITMAP;
+            if let Some(byte) = differing_byte {
+                other[byte] = 0;
+            }
+            let other = hide_slice_mut(&mut other);
+            macro_rules! bench_compare {
+                ($($op:ident),*) => {
+                    with_hidden_bitmap_mut(|bitmap| {
+                        $(
+                            g.bench_function(BenchmarkId::new(stringify!($op), name), |b| {
+                                b.iter(|| {
+                                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                    pessimize::assume_accessed(&mut other.as_mut_ptr());
+                                    pessimize::consume(bit_test::$op(bitmap, other));
+                                })
+                            });
+                        )*
+                    });
+                };
+            }
+            bench_compare!(bitmaps_equal, is_subset, is_disjoint);
+        }
+    }
+
+    // Sum the values of all bits of the bitmap, getting each bit either as an
+    // integer or as a boolean that is then converted to an integer
+    {
+        let mut g = c.benchmark_group("sum_bits");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        with_hidden_bitmap_mut(|bitmap| {
+            g.bench_function("bit_get_u8", |b| {
+                b.iter(|| {
+                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                    let mut sum = 0usize;
+                    for idx in 0..bitmap.len() * 8 {
+                        sum += bit_test::bit_get_u8(bitmap, idx) as usize;
+                    }
+                    pessimize::consume(sum);
+                })
+            });
+            macro_rules! bench_sum_bits_as_u8 {
+                ($($op:ident),*) => {
+                    $(
+                        g.bench_function(concat!(stringify!($op), "_as_u8"), |b| {
+                            b.iter(|| {
+                                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                let mut sum = 0usize;
+                                for idx in 0..bitmap.len() * 8 {
+                                    sum += bit_test::$op(bitmap, idx) as u8 as usize;
+                                }
+                                pessimize::consume(sum);
+                            })
+                        });
+                    )*
+                };
+            }
+            bench_sum_bits_as_u8!(bit_test_naive, bit_test_const_table);
+        });
+    }
+
+    // Count the set bits of a bitmap with an unpredictable pseudo-random
+    // pattern or a perfectly predictable all-ones pattern, either using a
+    // data-dependent branch or branch-free arithmetic
+    //
+    // Optimization barriers are used so that the optimizer cannot turn the
+    // branch of the branching version into a conditional move.
+    {
+        let mut g = c.benchmark_group("branch_predict");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        let mut random = [0u8; BITMAP.len()];
+        let mut rng_state = 0x2545_f491_4f6c_dd1d;
+        for byte in &mut random {
+            *byte = xorshift64(&mut rng_state) as u8;
+        }
+        for (name, mut bitmap) in [("random", random), ("all_ones", [u8::MAX; BITMAP.len()])] {
+            let bitmap = hide_slice_mut(&mut bitmap);
+            g.bench_function(BenchmarkId::new("bit_test_naive_branch", name), |b| {
+                b.iter(|| {
+                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                    let mut count = 0usize;
+                    for idx in 0..bitmap.len() * 8 {
+                        if bit_test::bit_test_naive(bitmap, idx) {
+                            count = pessimize::hide(count + 1);
+                        }
+                    }
+                    pessimize::consume(count);
+                })
+            });
+            g.bench_function(BenchmarkId::new("bit_get_u8_branchless", name), |b| {
+                b.iter(|| {
+                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                    let mut count = 0usize;
+                    for idx in 0..bitmap.len() * 8 {
+                        count = pessimize::hide(count + bit_test::bit_get_u8(bitmap, idx) as usize);
+                    }
+                    pessimize::consume(count);
+                })
+            });
+        }
+    }
+
+    // Like linear_all and popcount, but with a bitmap that starts at various
+    // offsets from a 64-byte boundary
+    //
+    // The byte-granular functions shouldn't care, but functions which process
+    // the bitmap in larger chunks may be affected by misaligned loads.
+    {
+        #[repr(C, align(64))]
+        struct AlignedBuffer([u8; BITMAP.len() + 8]);
+        let mut g = c.benchmark_group("misaligned");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        for offset in 0..=8 {
+            let mut backing = AlignedBuffer([42; BITMAP.len() + 8]);
+            let bitmap = hide_slice_mut(&mut backing.0[offset..offset + BITMAP.len()]);
+            bench_check_linear_all!(
+                g,
+                bitmap,
+                |op| BenchmarkId::new(op, offset);
+                bit_test_naive,
+                bit_test_const_table
+            );
+            macro_rules! bench_popcount {
+                ($($op:ident),*) => {
+                    $(
+                        g.bench_function(BenchmarkId::new(stringify!($op), offset), |b| {
+                            b.iter(|| {
+                                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                                pessimize::consume(bit_test::$op(bitmap));
+                            })
+                        });
+                    )*
+                };
+            }
+            bench_popcount!(count_ones, count_ones_u64);
+            #[cfg(feature = "portable_simd")]
+            bench_popcount!(count_ones_simd);
+            #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+            bench_popcount!(count_ones_neon);
+        }
+    }
+
+    // Like hidden_constant, but for the generic functions instantiated with
+    // storage words of various widths
+    {
+        let mut g = c.benchmark_group("generic_width");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        macro_rules! bench_generic_width {
+            ($($word:ty),*) => {
+                $({
+                    const WORD_SIZE: usize = std::mem::size_of::<$word>();
+                    let word = <$word>::from_le_bytes([42; WORD_SIZE]);
+                    let mut words = vec![word; BITMAP.len() / WORD_SIZE];
+                    let words = hide_slice_mut(&mut words);
+                    g.bench_function(BenchmarkId::new("bit_test_generic", stringify!($word)), |b| {
+                        b.iter(|| {
+                            let [i1, i2, i3, i4] = hidden_indices();
+                            let [o1, o2, o3, o4] = [
+                                bit_test::bit_test_generic(words, i1),
+                                bit_test::bit_test_generic(words, i2),
+                                bit_test::bit_test_generic(words, i3),
+                                bit_test::bit_test_generic(words, i4),
+                            ];
+                            pessimize::consume(o1);
+                            pessimize::consume(o2);
+                            pessimize::consume(o3);
+                            pessimize::consume(o4);
+                        })
+                    });
+                    bench_change_generic_width!($word, words, bit_set_generic, bit_clear_generic);
+                })*
+            };
+        }
+        macro_rules! bench_change_generic_width {
+            ($word:ty, $words:ident, $($op:ident),*) => {
+                $(
+                    g.bench_function(BenchmarkId::new(stringify!($op), stringify!($word)), |b| {
+                        b.iter(|| {
+                            let [i1, i2, i3, i4] = hidden_indices();
+                            bit_test::$op::<$word>($words, i1);
+                            bit_test::$op::<$word>($words, i2);
+                            bit_test::$op::<$word>($words, i3);
+                            bit_test::$op::<$word>($words, i4);
+                            pessimize::assume_accessed(&mut $words.as_mut_ptr());
+                        })
+                    });
+                )*
+            };
+        }
+        bench_generic_width!(u8, u16, u32, u64, usize);
+    }
+
+    // Like find_first_set, but searching for the last set bit from the end of
+    // the bitmap
+    //
+    // Throughput is measured in bits scanned before the search terminates.
+    {
+        let mut g = c.benchmark_group("find_last_set");
+        let num_bits = BITMAP.len() * 8;
+        for (name, set_bit) in [
+            ("first", Some(0)),
+            ("middle", Some(num_bits / 2)),
+            ("last", Some(num_bits - 1)),
+            ("none", None),
+        ] {
+            let mut bitmap = [0u8; BITMAP.len()];
+            if let Some(idx) = set_bit {
+                bit_test::bit_set_naive(&mut bitmap, idx);
+            }
+            let bitmap = hide_slice_mut(&mut bitmap);
+            g.throughput(Throughput::Elements(
+                set_bit.map_or(num_bits, |idx| num_bits - idx) as u64,
+            ));
+            g.bench_function(BenchmarkId::new("find_last_set", name), |b| {
+                b.iter(|| {
+                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                    pessimize::consume(bit_test::find_last_set(bitmap).unwrap_or(usize::MAX));
+                })
+            });
+        }
+    }
+
+    // Count the set bits within ranges of various lengths of a half-full
+    // pseudo-random bitmap
+    {
+        let mut g = c.benchmark_group("count_ones_in_range");
+        let mut bitmap = [0u8; BITMAP.len()];
+        let mut rng_state = 0x2545_f491_4f6c_dd1d;
+        for byte in &mut bitmap {
+            *byte = xorshift64(&mut rng_state) as u8;
+        }
+        let bitmap = hide_slice_mut(&mut bitmap);
+        const START: usize = 3;
+        for len in [1, 8, 64, 512, 4096, BITMAP.len() * 8 - START] {
+            g.throughput(Throughput::Elements(len as u64));
+            g.bench_function(BenchmarkId::new("count_ones_in_range", len), |b| {
+                b.iter(|| {
+                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                    let start = pessimize::hide(START);
+                    pessimize::consume(bit_test::count_ones_in_range(bitmap, start, start + len));
+                })
+            });
+        }
+    }
+
+    // Like hidden_constant, but each probe targets one of several bitmaps,
+    // which is selected by a hidden index
+    //
+    // This models a workload where each request probes one of K permission
+    // bitmaps, and measures the overhead of selecting the bitmap with respect
+    // to probing a single bitmap.
+    {
+        const NUM_BITMAPS: usize = 4;
+        let mut g = c.benchmark_group("select");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        let indices = [123, 456, 789, 1011];
+        let hidden_probes = || {
+            let [i1, i2, i3, i4] = indices;
+            [
+                (pessimize::hide(0), pessimize::hide(i1)),
+                (pessimize::hide(1), pessimize::hide(i2)),
+                (pessimize::hide(2), pessimize::hide(i3)),
+                (pessimize::hide(3), pessimize::hide(i4)),
+            ]
+        };
+        let mut storage = vec![BITMAP; NUM_BITMAPS];
+        let mut bitmaps = storage
+            .iter_mut()
+            .map(|bitmap| &*hide_slice_mut(bitmap))
+            .collect::<Vec<_>>();
+        let bitmaps = &*hide_slice_mut(&mut bitmaps);
+        g.bench_function("bit_test_const_table", |b| {
+            b.iter(|| {
+                let [(_, i1), (_, i2), (_, i3), (_, i4)] = hidden_probes();
+                let [o1, o2, o3, o4] = [
+                    bit_test::bit_test_const_table(bitmaps[0], i1),
+                    bit_test::bit_test_const_table(bitmaps[0], i2),
+                    bit_test::bit_test_const_table(bitmaps[0], i3),
+                    bit_test::bit_test_const_table(bitmaps[0], i4),
+                ];
+                pessimize::consume(o1);
+                pessimize::consume(o2);
+                pessimize::consume(o3);
+                pessimize::consume(o4);
+            })
+        });
+        g.bench_function("bit_test_select", |b| {
+            b.iter(|| {
+                let [(w1, i1), (w2, i2), (w3, i3), (w4, i4)] = hidden_probes();
+                let [o1, o2, o3, o4] = [
+                    bit_test::bit_test_select(bitmaps, w1, i1),
+                    bit_test::bit_test_select(bitmaps, w2, i2),
+                    bit_test::bit_test_select(bitmaps, w3, i3),
+                    bit_test::bit_test_select(bitmaps, w4, i4),
+                ];
+                pessimize::consume(o1);
+                pessimize::consume(o2);
+                pessimize::consume(o3);
+                pessimize::consume(o4);
+            })
+        });
+        g.bench_function("bit_test_select_unchecked", |b| {
+            b.iter(|| {
+                let [(w1, i1), (w2, i2), (w3, i3), (w4, i4)] = hidden_probes();
+                let [o1, o2, o3, o4] = unsafe {
+                    [
+                        bit_test::bit_test_select_unchecked(bitmaps, w1, i1),
+                        bit_test::bit_test_select_unchecked(bitmaps, w2, i2),
+                        bit_test::bit_test_select_unchecked(bitmaps, w3, i3),
+                        bit_test::bit_test_select_unchecked(bitmaps, w4, i4),
+                    ]
+                };
+                pessimize::consume(o1);
+                pessimize::consume(o2);
+                pessimize::consume(o3);
+                pessimize::consume(o4);
+            })
+        });
+    }
+
+    // Like hidden_constant, but accessing the bitmap through a raw pointer and
+    // a separately hidden length, either with a manual bounds check or without
+    // any bounds check, to isolate the cost of the slice abstraction
+    {
+        let mut g = c.benchmark_group("ptr_access");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        let mut bitmap = BITMAP;
+        let ptr = pessimize::hide(bitmap.as_mut_ptr());
+        let len = pessimize::hide(bitmap.len());
+        g.bench_function("bit_test_ptr_checked", |b| {
+            b.iter(|| {
+                let [i1, i2, i3, i4] = hidden_indices();
+                let test = |idx| {
+                    assert!(idx < len * 8);
+                    unsafe { bit_test::bit_test_ptr(ptr, idx) }
+                };
+                let [o1, o2, o3, o4] = [test(i1), test(i2), test(i3), test(i4)];
+                pessimize::consume(o1);
+                pessimize::consume(o2);
+                pessimize::consume(o3);
+                pessimize::consume(o4);
+            })
+        });
+        g.bench_function("bit_test_ptr", |b| {
+            b.iter(|| {
+                let [i1, i2, i3, i4] = hidden_indices();
+                let [o1, o2, o3, o4] = unsafe {
+                    [
+                        bit_test::bit_test_ptr(ptr, i1),
+                        bit_test::bit_test_ptr(ptr, i2),
+                        bit_test::bit_test_ptr(ptr, i3),
+                        bit_test::bit_test_ptr(ptr, i4),
+                    ]
+                };
+                pessimize::consume(o1);
+                pessimize::consume(o2);
+                pessimize::consume(o3);
+                pessimize::consume(o4);
+            })
+        });
+        macro_rules! bench_change_ptr {
+            ($($op:ident),*) => {
+                $(
+                    g.bench_function(concat!(stringify!($op), "_checked"), |b| {
+                        b.iter(|| {
+                            let [i1, i2, i3, i4] = hidden_indices();
+                            let change = |idx| {
+                                assert!(idx < len * 8);
+                                unsafe { bit_test::$op(ptr, idx) }
+                            };
+                            change(i1);
+                            change(i2);
+                            change(i3);
+                            change(i4);
+                            pessimize::assume_accessed(&mut { ptr });
+                        })
+                    });
+                    g.bench_function(stringify!($op), |b| {
+                        b.iter(|| {
+                            let [i1, i2, i3, i4] = hidden_indices();
+                            unsafe {
+                                bit_test::$op(ptr, i1);
+                                bit_test::$op(ptr, i2);
+                                bit_test::$op(ptr, i3);
+                                bit_test::$op(ptr, i4);
+                            }
+                            pessimize::assume_accessed(&mut { ptr });
+                        })
+                    });
+                )*
+            };
+        }
+        bench_change_ptr!(bit_set_ptr, bit_clear_ptr);
+    }
+
+    // Copy almost all bits of a bitmap into another bitmap, with both ranges
+    // starting on a byte boundary, at the same offset within a byte, or at
+    // different offsets within a byte
+    {
+        let mut g = c.benchmark_group("copy_bits");
+        let count = (BITMAP.len() - 1) * 8;
+        g.throughput(Throughput::Elements(count as u64));
+        let mut src = BITMAP;
+        let src = hide_slice_mut(&mut src);
+        for (name, src_start, dst_start) in [
+            ("aligned", 0, 0),
+            ("same_shift", 3, 3),
+            ("differing_shift", 3, 5),
+        ] {
+            with_hidden_bitmap_mut(|dst| {
+                g.bench_function(BenchmarkId::new("copy_bits", name), |b| {
+                    b.iter(|| {
+                        pessimize::assume_accessed(&mut src.as_mut_ptr());
+                        let (src_start, dst_start) =
+                            (pessimize::hide(src_start), pessimize::hide(dst_start));
+                        bit_test::copy_bits(dst, dst_start, src, src_start, count);
+                        pessimize::assume_accessed(&mut dst.as_mut_ptr());
+                    })
+                });
+            });
+        }
+    }
+
+    // Like hidden_constant, but comparing regular accessors with accessors
+    // that wrap the index around the power-of-two bitmap length
+    {
+        let mut g = c.benchmark_group("pow2_index");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        assert!(BITMAP.len().is_power_of_two());
+        bench_check_hidden_constant!(g, "": bit_test_naive, bit_test_pow2);
+        bench_change_hidden_constant!(
+            g, "": bit_set_naive,
+            bit_set_pow2,
+            bit_clear_naive,
+            bit_clear_pow2
+        );
+    }
+
+    // Count the bits of an atomic bitmap from one thread while several other
+    // threads keep modifying it
+    //
+    // Only the counting thread is timed, throughput is measured in bits
+    // counted per second.
+    {
+        use bit_test::atomic;
+        use std::{
+            sync::atomic::{AtomicBool, AtomicU8, Ordering},
+            thread,
+            time::Instant,
+        };
+        const NUM_MUTATORS: usize = 3;
+        let mut g = c.benchmark_group("atomic_count");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        let bitmap = BITMAP.iter().map(|&b| AtomicU8::new(b)).collect::<Vec<_>>();
+        let bitmap = &bitmap[..];
+        for num_mutators in [0, NUM_MUTATORS] {
+            for order in [Ordering::Relaxed, Ordering::SeqCst] {
+                g.bench_function(
+                    format!("atomic_count_ones/{num_mutators}_mutators/{order:?}"),
+                    |b| {
+                        b.iter_custom(|iters| {
+                            let stop = AtomicBool::new(false);
+                            let stop = &stop;
+                            thread::scope(|s| {
+                                for thread_idx in 0..num_mutators {
+                                    s.spawn(move || {
+                                        let mut rng_state =
+                                            0x2545_f491_4f6c_dd1d + thread_idx as u64;
+                                        while !stop.load(Ordering::Relaxed) {
+                                            let rand = xorshift64(&mut rng_state);
+                                            let idx = (rand >> 1) as usize % (bitmap.len() * 8);
+                                            if rand & 1 == 0 {
+                                                atomic::atomic_bit_set(
+                                                    bitmap,
+                                                    idx,
+                                                    Ordering::Relaxed,
+                                                );
+                                            } else {
+                                                atomic::atomic_bit_clear(
+                                                    bitmap,
+                                                    idx,
+                                                    Ordering::Relaxed,
+                                                );
+                                            }
+                                        }
+                                    });
+                                }
+                                let start = Instant::now();
+                                for _ in 0..iters {
+                                    pessimize::consume(atomic::atomic_count_ones(bitmap, order));
+                                }
+                                let elapsed = start.elapsed();
+                                stop.store(true, Ordering::Relaxed);
+                                elapsed
+                            })
+                        })
+                    },
+                );
+            }
+        }
+    }
+
+    // Like hidden_constant, but isolating the cost of splitting the bit index
+    // into a byte index and a bit offset: division and remainder, shift and
+    // mask, or shift and mask by an amount that the optimizer cannot see
+    {
+        let mut g = c.benchmark_group("index_math");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        #[inline]
+        fn bit_test_hidden_shift(bitmap: &[u8], idx: usize) -> bool {
+            let shift = pessimize::hide(3u32);
+            let offset_mask = pessimize::hide(7usize);
+            bitmap[idx >> shift] & (1 << (idx & offset_mask)) != 0
+        }
+        macro_rules! bench_index_math {
+            ($($op:path),*) => {
+                with_hidden_bitmap_mut(|bitmap| {
+                    $(
+                        g.bench_function(stringify!($op).rsplit("::").next().unwrap(), |b| {
+                            b.iter(|| {
+                                let [i1, i2, i3, i4] = hidden_indices();
+                                let [o1, o2, o3, o4] = [
+                                    $op(bitmap, i1),
+                                    $op(bitmap, i2),
+                                    $op(bitmap, i3),
+                                    $op(bitmap, i4),
+                                ];
+                                pessimize::consume(o1);
+                                pessimize::consume(o2);
+                                pessimize::consume(o3);
+                                pessimize::consume(o4);
+                            })
+                        });
+                    )*
+                });
+            };
+        }
+        bench_index_math!(
+            bit_test::bit_test_naive,
+            bit_test::bit_test_shift_naive,
+            bit_test_hidden_shift
+        );
+    }
+
+    // Like random_access, but with a bitmap that is too large to fit in cache,
+    // comparing plain bit tests with bit tests that prefetch the byte targeted
+    // by a later access of the index stream
+    #[cfg(target_arch = "x86_64")]
+    {
+        const NUM_INDICES: usize = 4096;
+        const BITMAP_LEN: usize = 64 * 1024 * 1024;
+        let mut g = c.benchmark_group("prefetch");
+        g.throughput(Throughput::Elements(NUM_INDICES as u64));
+        let mut bitmap = vec![42u8; BITMAP_LEN];
+        let bitmap = hide_slice_mut(&mut bitmap);
+        let mut rng_state = 0xd1b5_4a32_d192_ed03;
+        let mut indices = (0..NUM_INDICES)
+            .map(|_| xorshift64(&mut rng_state) as usize % (BITMAP_LEN * 8))
+            .collect::<Vec<_>>();
+        let indices = hide_slice_mut(&mut indices);
+        g.bench_function("bit_test_const_table", |b| {
+            b.iter(|| {
+                for &idx in indices.iter() {
+                    pessimize::consume(bit_test::bit_test_const_table(bitmap, idx));
+                }
+            })
+        });
+        for distance in [1, 4, 16] {
+            g.bench_function(BenchmarkId::new("bit_test_prefetch", distance), |b| {
+                b.iter(|| {
+                    for (i, &idx) in indices.iter().enumerate() {
+                        // Wrap around so that the first indices are prefetched
+                        // for the next iteration
+                        let next_idx = indices[(i + distance) % NUM_INDICES];
+                        pessimize::consume(bit_test::bit_test_prefetch(bitmap, idx, next_idx));
+                    }
+                })
+            });
+        }
+    }
+
+    // Allocate every slot of an initially empty bitmap, one slot at a time
+    //
+    // Each allocation scans the bitmap from the start, so the total cost grows
+    // quadratically with the bitmap size, and a small bitmap is used to keep
+    // the benchmark fast. Throughput is measured in allocations.
+    {
+        const BITMAP_LEN: usize = 512;
+        let mut g = c.benchmark_group("alloc_first_clear");
+        g.throughput(Throughput::Elements((BITMAP_LEN * 8) as u64));
+        let mut bitmap = [0u8; BITMAP_LEN];
+        let bitmap = hide_slice_mut(&mut bitmap);
+        g.bench_function("until_full", |b| {
+            b.iter(|| {
+                bitmap.fill(0);
+                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                while let Some(idx) = bit_test::alloc_first_clear(bitmap) {
+                    pessimize::consume(idx);
+                }
+                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+            })
+        });
+    }
+
+    // Free every slot of a fully allocated bitmap, in allocations of various
+    // sizes
+    {
+        let mut g = c.benchmark_group("free_range");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        for alloc_bits in [8, 64, 4096] {
+            with_hidden_bitmap_mut(|bitmap| {
+                g.bench_function(BenchmarkId::from_parameter(alloc_bits), |b| {
+                    b.iter(|| {
+                        bitmap.fill(u8::MAX);
+                        pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                        let alloc_bits = pessimize::hide(alloc_bits);
+                        for start in (0..bitmap.len() * 8).step_by(alloc_bits) {
+                            bit_test::free_range(bitmap, start, alloc_bits);
+                        }
+                        pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                    })
+                });
+            });
+        }
+    }
+
+    // Enumerate the differences between the bitmap and a copy of it where a
+    // few pseudo-random bits were flipped
+    {
+        const NUM_CHANGES: usize = 16;
+        let mut g = c.benchmark_group("diff_set_bits");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        let mut old = BITMAP;
+        let mut new = BITMAP;
+        let mut rng_state = 0xd1b5_4a32_d192_ed03;
+        for _ in 0..NUM_CHANGES {
+            let idx = xorshift64(&mut rng_state) as usize % (BITMAP.len() * 8);
+            bit_test::bit_toggle_naive(&mut new, idx);
+        }
+        let old = hide_slice_mut(&mut old);
+        let new = hide_slice_mut(&mut new);
+        g.bench_function("diff_set_bits", |b| {
+            b.iter(|| {
+                pessimize::assume_accessed(&mut old.as_mut_ptr());
+                pessimize::assume_accessed(&mut new.as_mut_ptr());
+                let (newly_set, newly_cleared) = bit_test::diff_set_bits(old, new);
+                for idx in newly_set.chain(newly_cleared) {
+                    pessimize::consume(idx);
+                }
+            })
+        });
+    }
+
+    // Run bit searches and counts over pseudo-random bitmaps with various
+    // fractions of set bits, from sparse to dense
+    //
+    // Throughput is measured in bits scanned, which for find_first_set stops
+    // at the first set bit.
+    #[cfg(feature = "std")]
+    {
+        let mut g = c.benchmark_group("density");
+        for percent_set in [1, 10, 50, 90] {
+            let mut rng_state = 0x2545_f491_4f6c_dd1d;
+            let bitmap = (0..BITMAP.len() * 8)
+                .map(|_| xorshift64(&mut rng_state) % 100 < percent_set)
+                .collect::<bit_test::Bitmap>();
+            let mut bytes = [0u8; BITMAP.len()];
+            for idx in bitmap.iter_ones() {
+                bit_test::bit_set_naive(&mut bytes, idx);
+            }
+            let bytes = hide_slice_mut(&mut bytes);
+            let id = |name| BenchmarkId::new(name, format!("{percent_set}%"));
+            let first_set = bit_test::find_first_set(bytes);
+            g.throughput(Throughput::Elements(
+                first_set.map_or(BITMAP.len() * 8, |idx| idx + 1) as u64,
+            ));
+            g.bench_function(id("find_first_set"), |b| {
+                b.iter(|| {
+                    pessimize::assume_accessed(&mut bytes.as_mut_ptr());
+                    pessimize::consume(bit_test::find_first_set(bytes).unwrap_or(usize::MAX));
+                })
+            });
+            g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+            g.bench_function(id("iter_ones"), |b| {
+                b.iter(|| {
+                    for idx in pessimize::hide(&bitmap).iter_ones() {
+                        pessimize::consume(idx);
+                    }
+                })
+            });
+            g.bench_function(id("count_ones"), |b| {
+                b.iter(|| {
+                    pessimize::assume_accessed(&mut bytes.as_mut_ptr());
+                    pessimize::consume(bit_test::count_ones(bytes));
+                })
+            });
+        }
+    }
+
+    // Summarize the bitmap at cache line granularity
+    #[cfg(feature = "std")]
+    {
+        let mut g = c.benchmark_group("summarize_lines");
+        g.throughput(Throughput::Elements((BITMAP.len() * 8) as u64));
+        for (name, set_bit) in [("empty", None), ("single_bit", Some(BITMAP.len() * 4))] {
+            let mut bitmap = [0u8; BITMAP.len()];
+            if let Some(idx) = set_bit {
+                bit_test::bit_set_naive(&mut bitmap, idx);
+            }
+            let bitmap = hide_slice_mut(&mut bitmap);
+            g.bench_function(name, |b| {
+                b.iter(|| {
+                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                    pessimize::consume(bit_test::summarize_lines(bitmap));
+                })
+            });
+        }
+        with_hidden_bitmap_mut(|bitmap| {
+            g.bench_function("dense", |b| {
+                b.iter(|| {
+                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                    pessimize::consume(bit_test::summarize_lines(bitmap));
+                })
+            });
+        });
+    }
+
+    // Like find_first_set, but comparing a flat bitmap with a hierarchical
+    // bitmap that can skip empty cache lines
+    #[cfg(feature = "std")]
+    {
+        let mut g = c.benchmark_group("hier_find_first_set");
+        let num_bits = BITMAP.len() * 8;
+        for (name, set_bit) in [
+            ("first", Some(0)),
+            ("middle", Some(num_bits / 2)),
+            ("last", Some(num_bits - 1)),
+            ("none", None),
+        ] {
+            let mut flat = [0u8; BITMAP.len()];
+            let mut hier = bit_test::HierBitmap::with_bytes(BITMAP.len());
+            if let Some(idx) = set_bit {
+                bit_test::bit_set_naive(&mut flat, idx);
+                hier.set(idx);
+            }
+            let flat = hide_slice_mut(&mut flat);
+            g.throughput(Throughput::Elements(
+                set_bit.map_or(num_bits, |idx| idx + 1) as u64,
+            ));
+            g.bench_function(BenchmarkId::new("flat", name), |b| {
+                b.iter(|| {
+                    pessimize::assume_accessed(&mut flat.as_mut_ptr());
+                    pessimize::consume(bit_test::find_first_set(flat).unwrap_or(usize::MAX));
+                })
+            });
+            g.bench_function(BenchmarkId::new("hier", name), |b| {
+                b.iter(|| {
+                    let hier = pessimize::hide(&hier);
+                    pessimize::consume(hier.find_first_set().unwrap_or(usize::MAX));
+                })
+            });
+        }
+    }
+
+    // Allocate a run of consecutive slots from a bitmap whose first three
+    // quarters are too fragmented to hold it, then free it
+    //
+    // Throughput is measured in bits scanned before the run is found.
+    {
+        let mut g = c.benchmark_group("alloc_run");
+        let fragmented_bytes = BITMAP.len() / 4 * 3;
+        let mut bitmap = [0u8; BITMAP.len()];
+        bitmap[..fragmented_bytes].fill(0b0101_0101);
+        let bitmap = hide_slice_mut(&mut bitmap);
+        for count in [2, 8, 64] {
+            g.throughput(Throughput::Elements((fragmented_bytes * 8 + count) as u64));
+            g.bench_function(BenchmarkId::from_parameter(count), |b| {
+                b.iter(|| {
+                    let count = pessimize::hide(count);
+                    let start = bit_test::alloc_run(bitmap, count).unwrap();
+                    pessimize::consume(start);
+                    bit_test::free_range(bitmap, start, count);
+                    pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                })
+            });
+        }
+    }
+
+    // Set a bit, then immediately test another bit that is either in the same
+    // byte, which requires forwarding the store to the load, or in the next
+    // cache line, which does not
+    {
+        let mut g = c.benchmark_group("store_forward");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        macro_rules! bench_store_forward {
+            ($($name:literal => $other_idx:expr),*) => {
+                with_hidden_bitmap_mut(|bitmap| {
+                    $(
+                        g.bench_function($name, |b| {
+                            b.iter(|| {
+                                for idx in hidden_indices() {
+                                    bit_test::bit_set_const_table(bitmap, idx);
+                                    let other_idx = $other_idx(idx);
+                                    pessimize::consume(bit_test::bit_test_const_table(bitmap, other_idx));
+                                }
+                            })
+                        });
+                    )*
+                });
+            };
+        }
+        bench_store_forward!(
+            "same_byte" => |idx: usize| idx ^ 1,
+            "next_line" => |idx: usize| idx + 64 * 8
+        );
+    }
+
+    // Move a bit to another position in the same byte or in a different byte,
+    // either with a single fused operation or by clearing then setting bits
+    {
+        let mut g = c.benchmark_group("move_bit");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        #[inline]
+        fn clear_then_set(bitmap: &mut [u8], from: usize, to: usize) {
+            bit_test::bit_clear_const_table(bitmap, from);
+            bit_test::bit_set_const_table(bitmap, to);
+        }
+        macro_rules! bench_move_bit {
+            ($($name:literal => $op:path),*) => {
+                with_hidden_bitmap_mut(|bitmap| {
+                    $(
+                        g.bench_function(format!("{}/same_byte", $name), |b| {
+                            b.iter(|| {
+                                for idx in hidden_indices() {
+                                    $op(bitmap, idx, idx ^ 1);
+                                }
+                                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                            })
+                        });
+                        g.bench_function(format!("{}/other_byte", $name), |b| {
+                            b.iter(|| {
+                                for idx in hidden_indices() {
+                                    $op(bitmap, idx, idx + 8);
+                                }
+                                pessimize::assume_accessed(&mut bitmap.as_mut_ptr());
+                            })
+                        });
+                    )*
+                });
+            };
+        }
+        bench_move_bit!(
+            "move_bit" => bit_test::move_bit,
+            "clear_then_set" => clear_then_set
+        );
+    }
+
+    // Probe one bit per page of a bitmap that is larger than the L1 cache
+    //
+    // With a stride of exactly one page, all probed bytes map to the same
+    // cache set, so they evict each other even though the cache could hold
+    // them all. Adding one cache line to the stride spreads the probed bytes
+    // across cache sets, which shows the cost of these conflict misses.
+    {
+        const BITMAP_LEN: usize = 4 * 1024 * 1024;
+        const PAGE_SIZE: usize = 4096;
+        let mut g = c.benchmark_group("conflict_stride");
+        let mut bitmap = vec![42u8; BITMAP_LEN];
+        let bitmap = hide_slice_mut(&mut bitmap);
+        for (name, byte_stride) in [("page", PAGE_SIZE), ("page_plus_line", PAGE_SIZE + 64)] {
+            let num_probes = BITMAP_LEN / byte_stride;
+            g.throughput(Throughput::Elements(num_probes as u64));
+            g.bench_function(name, |b| {
+                b.iter(|| {
+                    let bit_stride = pessimize::hide(byte_stride * 8);
+                    for idx in (0..BITMAP_LEN * 8).step_by(bit_stride) {
+                        pessimize::consume(bit_test::bit_test_const_table(bitmap, idx));
+                    }
+                })
+            });
+        }
+    }
+
+    // Compare byte-granular bit packing, where indices are split with a shift
+    // and a mask, with packing 6 bits per byte, which requires a true division
+    // and remainder
+    {
+        let mut g = c.benchmark_group("nonpow2_pack");
+        g.throughput(Throughput::Elements(UNROLL_FACTOR as u64));
+        bench_check_hidden_constant!(g, "hidden_constant/": bit_test_shift_naive, bit_test_base6);
+        bench_change_hidden_constant!(
+            g, "hidden_constant/": bit_set_naive,
+            bit_set_base6,
+            bit_clear_naive,
+            bit_clear_base6
+        );
+
+        // Each byte holds fewer bits with 6-bit packing, so throughput is
+        // measured in bits scanned rather than bytes
+        macro_rules! bench_check_linear_all_bits {
+            ($($op:ident => $bits_per_byte:expr),*) => {
+                with_hidden_bitmap_mut(|bitmap| {
+                    $(
+                        let num_bits = bitmap.len() * $bits_per_byte;
+                        g.throughput(Throughput::Elements(num_bits as u64));
+                        g.bench_function(concat!("linear_all/", stringify!($op)), |b| {
+                            b.iter(|| {
+                                for idx in 0..num_bits {
+                                    pessimize::consume(bit_test::$op(bitmap, idx));
+                                }
+                            })
+                        });
+                    )*
+                });
+            };
+        }
+        bench_check_linear_all_bits!(bit_test_shift_naive => 8, bit_test_base6 => 6);
+    }
+
+    // Test the same hidden index in 8 independent bitmaps, as done by
+    // permission systems which store one bitmap per attribute
+    //
+    // The index computation only needs to be done once for all bitmaps, and the
+    // 8 loads are independent so they can be carried out in parallel. For
+    // comparison, the single_bitmap benchmarks test 8 different hidden indices
+    // in a single bitmap, where the index computation can't be shared.
+    {
+        const NUM_BITMAPS: usize = 8;
+        let mut g = c.benchmark_group("multi_bitmap");
+        g.throughput(Throughput::Elements(NUM_BITMAPS as u64));
+        let mut storage = vec![BITMAP; NUM_BITMAPS];
+        let mut bitmaps = storage.iter_mut().map(|bitmap| hide_slice_mut(bitmap));
+        let bitmaps: [&mut [u8]; NUM_BITMAPS] = std::array::from_fn(|_| bitmaps.next().unwrap());
+        macro_rules! bench_check_multi_bitmap {
+            ($($op:ident),*) => {
+                $(
+                    g.bench_function(concat!("multi_bitmap/", stringify!($op)), |b| {
+                        b.iter(|| {
+                            let idx = pessimize::hide(123);
+                            for bitmap in &bitmaps {
+                                pessimize::consume(bit_test::$op(bitmap, idx));
+                            }
+                        })
+                    });
+                )*
+                with_hidden_bitmap_mut(|bitmap| {
+                    $(
+                        g.bench_function(concat!("single_bitmap/", stringify!($op)), |b| {
+                            b.iter(|| {
+                                for idx in [123, 456, 789, 1011, 1213, 1415, 1617, 1819] {
+                                    pessimize::consume(bit_test::$op(bitmap, pessimize::hide(idx)));
+                                }
+                            })
+                        });
+                    )*
+                });
+            };
+        }
+        bench_check_multi_bitmap!(bit_test_naive, bit_test_const_table, bit_test_static_table);
+    }
+
+    // Read and write a BoolVec at pseudo-random indices, compared with a
+    // Vec<bool> of the same length, which takes 8x more memory but doesn't need
+    // any bit manipulation
+    #[cfg(feature = "std")]
+    {
+        const NUM_INDICES: usize = 4096;
+        const LEN: usize = BITMAP.len() * 8;
+        let mut g = c.benchmark_group("bool_vec");
+        g.throughput(Throughput::Elements(NUM_INDICES as u64));
+        let mut rng_state = 0x2545_f491_4f6c_dd1d;
+        let values = (0..LEN)
+            .map(|_| xorshift64(&mut rng_state).is_multiple_of(2))
+            .collect::<Vec<_>>();
+        let mut indices = (0..NUM_INDICES)
+            .map(|_| xorshift64(&mut rng_state) as usize % LEN)
+            .collect::<Vec<_>>();
+        let indices = hide_slice_mut(&mut indices);
+        let mut bool_vec = values.iter().copied().collect::<bit_test::BoolVec>();
+        let mut vec_bool = values;
+        g.bench_function("get/BoolVec", |b| {
+            b.iter(|| {
+                for &idx in indices.iter() {
+                    pessimize::consume(bool_vec.get(idx).unwrap_or(false));
+                }
+            })
+        });
+        g.bench_function("get/Vec<bool>", |b| {
+            b.iter(|| {
+                for &idx in indices.iter() {
+                    pessimize::consume(vec_bool.get(idx).copied().unwrap_or(false));
+                }
+            })
+        });
+        g.bench_function("set/BoolVec", |b| {
+            b.iter(|| {
+                for &idx in indices.iter() {
+                    bool_vec.set(idx, idx % 2 == 0);
+                }
+                pessimize::consume(&bool_vec);
+            })
+        });
+        g.bench_function("set/Vec<bool>", |b| {
+            b.iter(|| {
+                for &idx in indices.iter() {
+                    vec_bool[idx] = idx % 2 == 0;
+                }
+                pessimize::assume_accessed(&mut vec_bool.as_mut_ptr());
+            })
+        });
+    }
+
+    // Test all 8 bits of a byte at a hidden location, either by loading the
+    // byte once through bit_test_byte and testing the other bits of the
+    // returned byte, or through 8 separate bit_test calls
+    //
+    // The optimizer may well merge the loads of the separate calls, since
+    // nothing is written to the bitmap in between, in which case both
+    // versions should perform the same.
+    {
+        let mut g = c.benchmark_group("byte_reuse");
+        g.throughput(Throughput::Elements(8));
+        with_hidden_bitmap_mut(|bitmap| {
+            g.bench_function("bit_test_byte", |b| {
+                b.iter(|| {
+                    let first_bit = pessimize::hide(123) * 8;
+                    let (first, byte) = bit_test::bit_test_byte(bitmap, first_bit);
+                    pessimize::consume(first);
+                    for bit in 1..8 {
+                        pessimize::consume(byte & (1 << bit) != 0);
+                    }
+                })
+            });
+            g.bench_function("bit_test_naive", |b| {
+                b.iter(|| {
+                    let first_bit = pessimize::hide(123) * 8;
+                    for bit in 0..8 {
+                        pessimize::consume(bit_test::bit_test_naive(bitmap, first_bit + bit));
+                    }
+                })
+            });
+        });
+    }
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,44 @@
+//! Bit operations over bitmaps that can be shared between threads
+//!
+//! The memory ordering of each operation is a parameter, so that the cost of
+//! the various orderings can be compared.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// Test a bit, `order` must be an ordering that is valid for atomic loads
+#[inline]
+pub fn atomic_bit_test(bitmap: &[AtomicU8], idx: usize, order: Ordering) -> bool {
+    bitmap[idx >> 3].load(order) & (1 << (idx & 7)) != 0
+}
+
+#[inline]
+pub fn atomic_bit_set(bitmap: &[AtomicU8], idx: usize, order: Ordering) {
+    bitmap[idx >> 3].fetch_or(1 << (idx & 7), order);
+}
+
+#[inline]
+pub fn atomic_bit_clear(bitmap: &[AtomicU8], idx: usize, order: Ordering) {
+    bitmap[idx >> 3].fetch_and(!(1 << (idx & 7)), order);
+}
+
+/// Set a bit and return its previous value
+#[inline]
+pub fn atomic_test_and_set(bitmap: &[AtomicU8], idx: usize, order: Ordering) -> bool {
+    let mask = 1 << (idx & 7);
+    bitmap[idx >> 3].fetch_or(mask, order) & mask != 0
+}
+
+/// Count the bits which are set, `order` must be an ordering that is valid for
+/// atomic loads
+///
+/// Each byte is loaded atomically, but the bitmap as a whole is not: if other
+/// threads modify the bitmap during the count, the result may not match the
+/// number of set bits at any single point in time. The count is thus only
+/// eventually consistent, i.e. it is exact once concurrent writes stop.
+#[inline]
+pub fn atomic_count_ones(bitmap: &[AtomicU8], order: Ordering) -> usize {
+    bitmap
+        .iter()
+        .map(|byte| byte.load(order).count_ones() as usize)
+        .sum()
+}
@@ -0,0 +1,53 @@
+//! Conversions between the bitmaps of this crate and those of the `bitvec`
+//! crate, so that both can be benchmarked on the same data
+//!
+//! Bit `i` of a bitmap of this crate is bit `i % 8` of byte `i / 8`, counting
+//! from the least significant bit, which is the layout of `BitVec<u8, Lsb0>`.
+
+use ::bitvec::{order::Lsb0, vec::BitVec, view::BitView};
+
+/// Copy the first `bit_len` bits of `bytes` into a bit vector with the same
+/// bit indices
+///
+/// # Panics
+///
+/// If `bytes` holds less than `bit_len` bits.
+///
+/// ```
+/// # use bit_test::{bit_test_naive, bitvec::to_bitvec};
+/// let bytes = [0b0000_0001, 0b1000_0010];
+/// let bits = to_bitvec(&bytes, 12);
+/// assert_eq!(bits.len(), 12);
+/// assert!(bits[0] && bit_test_naive(&bytes, 0));
+/// assert_eq!(bits.iter_ones().collect::<Vec<_>>(), [0, 9]);
+/// ```
+pub fn to_bitvec(bytes: &[u8], bit_len: usize) -> BitVec<u8, Lsb0> {
+    assert!(
+        bit_len <= bytes.len() * 8,
+        "{} bytes cannot hold {bit_len} bits",
+        bytes.len()
+    );
+    bytes.view_bits::<Lsb0>()[..bit_len].to_bitvec()
+}
+
+/// Copy a bit vector into bytes with the same bit indices, and return them
+/// along with the number of bits
+///
+/// The bit vector may start in the middle of its first byte, in which case its
+/// bits are shifted to start at bit 0. The padding bits of the last byte are
+/// clear, as expected by [`Bitmap::from_bytes()`](crate::Bitmap::from_bytes).
+///
+/// ```
+/// # use bit_test::bitvec::{from_bitvec, to_bitvec};
+/// let bytes = [0b0101_1001, 0b0000_0110];
+/// let bits = to_bitvec(&bytes, 11);
+/// assert_eq!(from_bitvec(&bits), (bytes.to_vec(), 11));
+///
+/// let (shifted, bit_len) = from_bitvec(&bits[3..].to_bitvec());
+/// assert_eq!((shifted, bit_len), (vec![0b1100_1011], 8));
+/// ```
+pub fn from_bitvec(bits: &BitVec<u8, Lsb0>) -> (Vec<u8>, usize) {
+    let mut bytes = vec![0; bits.len().div_ceil(8)];
+    bytes.view_bits_mut::<Lsb0>()[..bits.len()].copy_from_bitslice(bits);
+    (bytes, bits.len())
+}
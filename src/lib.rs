@@ -1,8 +1,25 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "portable_simd", feature(portable_simd))]
+
+pub mod atomic;
+#[cfg(feature = "bitvec")]
+pub mod bitvec;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod noinline;
+
 #[inline]
 pub fn bit_test_naive(bitmap: &[u8], idx: usize) -> bool {
     bitmap[idx / 8] & (1 << (idx % 8)) != 0
 }
 
+/// Like [`bit_test_naive()`], but splits the index using shifts and masks
+/// instead of division and remainder
+#[inline]
+pub fn bit_test_shift_naive(bitmap: &[u8], idx: usize) -> bool {
+    bitmap[idx >> 3] & (1 << (idx & 7)) != 0
+}
+
 #[inline]
 pub fn bit_set_naive(bitmap: &mut [u8], idx: usize) {
     bitmap[idx / 8] |= 1 << (idx % 8);
@@ -13,6 +30,78 @@ pub fn bit_clear_naive(bitmap: &mut [u8], idx: usize) {
     bitmap[idx / 8] &= !(1 << (idx % 8));
 }
 
+#[inline]
+pub fn bit_toggle_naive(bitmap: &mut [u8], idx: usize) {
+    bitmap[idx / 8] ^= 1 << (idx % 8);
+}
+
+#[inline]
+pub fn bit_test_and_set_naive(bitmap: &mut [u8], idx: usize) -> bool {
+    let byte = &mut bitmap[idx / 8];
+    let mask = 1 << (idx % 8);
+    let old = *byte & mask != 0;
+    *byte |= mask;
+    old
+}
+
+#[inline]
+pub fn bit_test_and_clear_naive(bitmap: &mut [u8], idx: usize) -> bool {
+    let byte = &mut bitmap[idx / 8];
+    let mask = 1 << (idx % 8);
+    let old = *byte & mask != 0;
+    *byte &= !mask;
+    old
+}
+
+/// Set a bit and tell if it was previously clear, e.g. to claim a free slot
+/// in a single-threaded allocator
+///
+/// This is [`bit_test_and_set_naive()`] with an inverted result, so that
+/// `true` means success. A bit that was already set stays set.
+///
+/// ```
+/// # use bit_test::try_set;
+/// let mut bitmap = [0b0000_0100];
+/// assert!(try_set(&mut bitmap, 1));
+/// assert!(!try_set(&mut bitmap, 2));
+/// assert_eq!(bitmap, [0b0000_0110]);
+/// ```
+#[inline]
+pub fn try_set(bitmap: &mut [u8], idx: usize) -> bool {
+    !bit_test_and_set_naive(bitmap, idx)
+}
+
+/// Clear a bit and tell if it was previously set, e.g. to release a slot
+/// that should have been allocated
+///
+/// ```
+/// # use bit_test::try_clear;
+/// let mut bitmap = [0b0000_0100];
+/// assert!(try_clear(&mut bitmap, 2));
+/// assert!(!try_clear(&mut bitmap, 1));
+/// assert_eq!(bitmap, [0]);
+/// ```
+#[inline]
+pub fn try_clear(bitmap: &mut [u8], idx: usize) -> bool {
+    bit_test_and_clear_naive(bitmap, idx)
+}
+
+#[inline]
+pub fn bit_assign_branching(bitmap: &mut [u8], idx: usize, value: bool) {
+    if value {
+        bit_set_naive(bitmap, idx)
+    } else {
+        bit_clear_naive(bitmap, idx)
+    }
+}
+
+#[inline]
+pub fn bit_assign_naive(bitmap: &mut [u8], idx: usize, value: bool) {
+    let byte = &mut bitmap[idx / 8];
+    let mask = 1 << (idx % 8);
+    *byte = (*byte & !mask) | (mask & 0u8.wrapping_sub(value as u8));
+}
+
 #[inline]
 pub fn bit_test_const_table(bitmap: &[u8], idx: usize) -> bool {
     bitmap[idx >> 3] & BIT_MASK[idx & 7] != 0
@@ -28,6 +117,36 @@ pub fn bit_clear_const_table(bitmap: &mut [u8], idx: usize) {
     bitmap[idx >> 3] &= UNSET_BIT_MASK[idx & 7]
 }
 
+#[inline]
+pub fn bit_toggle_const_table(bitmap: &mut [u8], idx: usize) {
+    bitmap[idx >> 3] ^= BIT_MASK[idx & 7]
+}
+
+#[inline]
+pub fn bit_test_and_set_const_table(bitmap: &mut [u8], idx: usize) -> bool {
+    let byte = &mut bitmap[idx >> 3];
+    let mask = BIT_MASK[idx & 7];
+    let old = *byte & mask != 0;
+    *byte |= mask;
+    old
+}
+
+#[inline]
+pub fn bit_test_and_clear_const_table(bitmap: &mut [u8], idx: usize) -> bool {
+    let byte = &mut bitmap[idx >> 3];
+    let mask = BIT_MASK[idx & 7];
+    let old = *byte & mask != 0;
+    *byte &= !mask;
+    old
+}
+
+#[inline]
+pub fn bit_assign_const_table(bitmap: &mut [u8], idx: usize, value: bool) {
+    let byte = &mut bitmap[idx >> 3];
+    let mask = BIT_MASK[idx & 7];
+    *byte = (*byte & !mask) | (mask & 0u8.wrapping_sub(value as u8));
+}
+
 const BIT_MASK: [u8; 8] = [1, 2, 4, 8, 16, 32, 64, 128];
 const UNSET_BIT_MASK: [u8; 8] = [
     255 - 1,
@@ -45,6 +164,15 @@ pub fn bit_test_static_table(bitmap: &[u8], idx: usize) -> bool {
     bitmap[idx >> 3] & BIT_MASK_STATIC[idx & 7] != 0
 }
 
+/// Like [`bit_test_const_table()`], but passes the table through
+/// [`black_box()`](core::hint::black_box) so that the optimizer cannot
+/// constant-propagate it, forcing a table lookup in memory
+#[inline]
+pub fn bit_test_boxed_table(bitmap: &[u8], idx: usize) -> bool {
+    let bit_mask = core::hint::black_box(&BIT_MASK);
+    bitmap[idx >> 3] & bit_mask[idx & 7] != 0
+}
+
 #[inline]
 pub fn bit_set_static_table(bitmap: &mut [u8], idx: usize) {
     bitmap[idx >> 3] |= BIT_MASK_STATIC[idx & 7]
@@ -55,5 +183,3214 @@ pub fn bit_clear_static_table(bitmap: &mut [u8], idx: usize) {
     bitmap[idx >> 3] &= UNSET_BIT_MASK_STATIC[idx & 7]
 }
 
+#[inline]
+pub fn bit_toggle_static_table(bitmap: &mut [u8], idx: usize) {
+    bitmap[idx >> 3] ^= BIT_MASK_STATIC[idx & 7]
+}
+
+#[inline]
+pub fn bit_test_and_set_static_table(bitmap: &mut [u8], idx: usize) -> bool {
+    let byte = &mut bitmap[idx >> 3];
+    let mask = BIT_MASK_STATIC[idx & 7];
+    let old = *byte & mask != 0;
+    *byte |= mask;
+    old
+}
+
+#[inline]
+pub fn bit_test_and_clear_static_table(bitmap: &mut [u8], idx: usize) -> bool {
+    let byte = &mut bitmap[idx >> 3];
+    let mask = BIT_MASK_STATIC[idx & 7];
+    let old = *byte & mask != 0;
+    *byte &= !mask;
+    old
+}
+
+#[inline]
+pub fn bit_assign_static_table(bitmap: &mut [u8], idx: usize, value: bool) {
+    let byte = &mut bitmap[idx >> 3];
+    let mask = BIT_MASK_STATIC[idx & 7];
+    *byte = (*byte & !mask) | (mask & 0u8.wrapping_sub(value as u8));
+}
+
 static BIT_MASK_STATIC: [u8; 8] = BIT_MASK;
 static UNSET_BIT_MASK_STATIC: [u8; 8] = UNSET_BIT_MASK;
+
+#[inline]
+pub fn bit_test_checked(bitmap: &[u8], idx: usize) -> Option<bool> {
+    check_bounds(bitmap, idx)
+        .ok()
+        .map(|()| bit_test_naive(bitmap, idx))
+}
+
+/// Like [`bit_test_naive()`], but without any bounds checking
+///
+/// # Safety
+///
+/// `idx` must be smaller than `bitmap.len() * 8`.
+#[inline]
+pub unsafe fn bit_test_unchecked(bitmap: &[u8], idx: usize) -> bool {
+    unsafe { *bitmap.get_unchecked(idx / 8) & (1 << (idx % 8)) != 0 }
+}
+
+#[inline]
+pub fn bit_set_checked(bitmap: &mut [u8], idx: usize) -> Result<(), OutOfBounds> {
+    check_bounds(bitmap, idx)?;
+    bit_set_naive(bitmap, idx);
+    Ok(())
+}
+
+#[inline]
+pub fn bit_clear_checked(bitmap: &mut [u8], idx: usize) -> Result<(), OutOfBounds> {
+    check_bounds(bitmap, idx)?;
+    bit_clear_naive(bitmap, idx);
+    Ok(())
+}
+
+#[inline]
+fn check_bounds(bitmap: &[u8], idx: usize) -> Result<(), OutOfBounds> {
+    check_bounds_bytes(bitmap.len(), idx)
+}
+
+/// Check that bit `idx` is within a bitmap of `len_bytes` bytes
+///
+/// On targets with a small `usize`, the number of bits of a bitmap may not
+/// fit in `usize`. Every index is then within the bitmap.
+#[inline]
+const fn check_bounds_bytes(len_bytes: usize, idx: usize) -> Result<(), OutOfBounds> {
+    match len_bytes.checked_mul(8) {
+        Some(bits) if idx >= bits => Err(OutOfBounds { idx, bits }),
+        _ => Ok(()),
+    }
+}
+
+const _: () = {
+    assert!(check_bounds_bytes(2, 15).is_ok());
+    assert!(check_bounds_bytes(2, 16).is_err());
+    assert!(check_bounds_bytes(usize::MAX / 4, usize::MAX).is_ok());
+    // Smallest bitmap whose number of bits wraps around to 0 in `usize`, on
+    // which a naive `idx < len * 8` check would reject every index
+    assert!(check_bounds_bytes(usize::MAX / 8 + 1, 0).is_ok());
+};
+
+/// Error returned when a bit index is outside of a bitmap
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OutOfBounds {
+    /// Bit index that was requested
+    pub idx: usize,
+
+    /// Number of bits in the bitmap
+    pub bits: usize,
+}
+
+impl core::fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "bit index {} is out of bounds for a bitmap of {} bits",
+            self.idx, self.bits
+        )
+    }
+}
+
+impl core::error::Error for OutOfBounds {}
+
+/// Owned bitmap which keeps track of how many of its bits are meaningful
+#[cfg(feature = "std")]
+#[derive(Clone, Default, Eq, Hash, PartialEq)]
+pub struct Bitmap {
+    bytes: Vec<u8>,
+    bit_len: usize,
+}
+
+#[cfg(feature = "std")]
+impl Bitmap {
+    /// Create a bitmap of `bit_len` bits, all initially clear
+    pub fn with_bits(bit_len: usize) -> Self {
+        Self {
+            bytes: vec![0; bit_len.div_ceil(8)],
+            bit_len,
+        }
+    }
+
+    /// Create a bitmap of `bit_len` bits where only the bits at `indices` are
+    /// set
+    ///
+    /// # Panics
+    ///
+    /// If any of the `indices` is out of bounds. See
+    /// [`try_from_indices()`](Self::try_from_indices) for a fallible version.
+    ///
+    /// ```
+    /// # use bit_test::Bitmap;
+    /// let bitmap = Bitmap::from_indices(8, [1, 3, 5]);
+    /// assert_eq!(bitmap.iter_ones().collect::<Vec<_>>(), [1, 3, 5]);
+    /// assert_eq!(bitmap.to_string(), "01010100");
+    /// ```
+    pub fn from_indices(bit_len: usize, indices: impl IntoIterator<Item = usize>) -> Self {
+        Self::try_from_indices(bit_len, indices).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`from_indices()`](Self::from_indices), but returns an error
+    /// instead of panicking if an index is out of bounds
+    pub fn try_from_indices(
+        bit_len: usize,
+        indices: impl IntoIterator<Item = usize>,
+    ) -> Result<Self, OutOfBounds> {
+        let mut bitmap = Self::with_bits(bit_len);
+        for idx in indices {
+            if idx >= bit_len {
+                return Err(OutOfBounds { idx, bits: bit_len });
+            }
+            bit_set_const_table(&mut bitmap.bytes, idx);
+        }
+        Ok(bitmap)
+    }
+
+    /// Number of bits in the bitmap
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bit_len
+    }
+
+    /// Truth that the bitmap contains no bits
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bit_len == 0
+    }
+
+    #[inline]
+    pub fn test(&self, idx: usize) -> bool {
+        self.check_index(idx);
+        bit_test_const_table(&self.bytes, idx)
+    }
+
+    #[inline]
+    pub fn set(&mut self, idx: usize) {
+        self.check_index(idx);
+        bit_set_const_table(&mut self.bytes, idx)
+    }
+
+    /// Like [`set()`](Self::set), but grows the bitmap to `idx + 1` bits if
+    /// `idx` is out of bounds instead of panicking
+    ///
+    /// The bitmap is grown in a single step, no matter how far `idx` is beyond
+    /// the end of the bitmap, and the bits in between are clear.
+    ///
+    /// ```
+    /// # use bit_test::Bitmap;
+    /// let mut bitmap = Bitmap::default();
+    /// bitmap.set_growing(1000);
+    /// assert_eq!(bitmap.len(), 1001);
+    /// assert!(bitmap.test(1000));
+    /// assert_eq!(bitmap.iter_ones().collect::<Vec<_>>(), [1000]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is `usize::MAX`, since the bitmap would then have more bits
+    /// than `usize` can count.
+    #[inline]
+    pub fn set_growing(&mut self, idx: usize) {
+        if idx >= self.bit_len {
+            self.resize(idx.checked_add(1).expect("bit index overflows usize"));
+        }
+        bit_set_const_table(&mut self.bytes, idx)
+    }
+
+    #[inline]
+    pub fn clear(&mut self, idx: usize) {
+        self.check_index(idx);
+        bit_clear_const_table(&mut self.bytes, idx)
+    }
+
+    /// Iterate over all bits of the bitmap, from index 0 to `len() - 1`
+    #[inline]
+    pub fn iter(&self) -> Bits<'_> {
+        Bits {
+            bytes: &self.bytes,
+            front: 0,
+            back: self.bit_len,
+        }
+    }
+
+    /// Iterate over the indices of the set bits of the bitmap, in ascending
+    /// order
+    ///
+    /// The scan stops at `len()`, so padding bits of the last byte are never
+    /// reported, even if they somehow ended up set.
+    ///
+    /// ```
+    /// # use bit_test::Bitmap;
+    /// let mut bitmap = Bitmap::with_bits(13);
+    /// bitmap.set(2);
+    /// bitmap.set(12);
+    /// assert_eq!(bitmap.iter_ones().collect::<Vec<_>>(), [2, 12]);
+    /// ```
+    #[inline]
+    pub fn iter_ones(&self) -> SetBits<'_> {
+        SetBits {
+            bitmap: &self.bytes,
+            front: 0,
+            back: self.bit_len,
+        }
+    }
+
+    /// Change the number of bits in the bitmap
+    ///
+    /// Bits that are added by growing the bitmap are initially clear, even if
+    /// they were previously set then removed by shrinking the bitmap.
+    ///
+    /// ```
+    /// # use bit_test::Bitmap;
+    /// let mut bitmap = Bitmap::with_bits(16);
+    /// bitmap.set(5);
+    /// bitmap.set(11);
+    /// bitmap.resize(4);
+    /// bitmap.resize(20);
+    /// assert_eq!(bitmap.len(), 20);
+    /// assert!(!bitmap.test(5));
+    /// assert!(!bitmap.test(11));
+    /// ```
+    pub fn resize(&mut self, new_bits: usize) {
+        // Bits beyond bit_len are kept clear at all times, so growing the
+        // bitmap only requires adding zeroed bytes
+        self.bytes.resize(new_bits.div_ceil(8), 0);
+        let shrunk = new_bits < self.bit_len;
+        self.bit_len = new_bits;
+        if shrunk {
+            self.clear_padding();
+        }
+    }
+
+    /// Append a bit at the end of the bitmap
+    ///
+    /// ```
+    /// # use bit_test::Bitmap;
+    /// let mut bitmap = Bitmap::default();
+    /// let bits = (0..17).map(|i| i % 3 == 0).collect::<Vec<_>>();
+    /// for &bit in &bits {
+    ///     bitmap.push(bit);
+    /// }
+    /// assert_eq!(bitmap.len(), 17);
+    /// for &bit in bits.iter().rev() {
+    ///     assert_eq!(bitmap.pop(), Some(bit));
+    /// }
+    /// assert_eq!(bitmap.pop(), None);
+    /// ```
+    pub fn push(&mut self, bit: bool) {
+        if self.bit_len.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        if bit {
+            bit_set_const_table(&mut self.bytes, self.bit_len);
+        }
+        self.bit_len += 1;
+    }
+
+    /// Remove the last bit of the bitmap and return it, if any
+    pub fn pop(&mut self) -> Option<bool> {
+        let idx = self.bit_len.checked_sub(1)?;
+        let bit = bit_test_const_table(&self.bytes, idx);
+        bit_clear_const_table(&mut self.bytes, idx);
+        self.bit_len = idx;
+        if idx.is_multiple_of(8) {
+            self.bytes.pop();
+        }
+        Some(bit)
+    }
+
+    /// Rotate the bits of the bitmap by `n` positions towards higher indices,
+    /// moving the bits that fall off the end back to the beginning
+    ///
+    /// Unlike the [`rotate_left()`] free function, this only rotates the first
+    /// `len()` bits, even if that is not a multiple of 8.
+    ///
+    /// ```
+    /// # use bit_test::Bitmap;
+    /// let bits = |s: &str| s.chars().map(|c| c == '1').collect::<Bitmap>();
+    /// let mut bitmap = bits("1100000001");
+    /// bitmap.rotate_left(3);
+    /// assert_eq!(bitmap, bits("0011100000"));
+    /// bitmap.rotate_right(4);
+    /// assert_eq!(bitmap, bits("1000000011"));
+    /// ```
+    pub fn rotate_left(&mut self, n: usize) {
+        if self.bit_len.is_multiple_of(8) {
+            rotate_left(&mut self.bytes, n);
+            return;
+        }
+        let n = n % self.bit_len;
+        let old = self.bytes.clone();
+        copy_bits(&mut self.bytes, n, &old, 0, self.bit_len - n);
+        copy_bits(&mut self.bytes, 0, &old, self.bit_len - n, n);
+    }
+
+    /// Rotate the bits of the bitmap by `n` positions towards lower indices,
+    /// moving the bits that fall off the beginning back to the end
+    ///
+    /// Unlike the [`rotate_right()`] free function, this only rotates the
+    /// first `len()` bits, even if that is not a multiple of 8.
+    pub fn rotate_right(&mut self, n: usize) {
+        if self.bit_len == 0 {
+            return;
+        }
+        self.rotate_left(self.bit_len - n % self.bit_len);
+    }
+
+    /// Flip all bits of the bitmap, turning it into the complement of the set
+    /// it represents
+    ///
+    /// Unlike the [`complement()`] free function, this keeps the padding bits
+    /// of the last byte clear, so that they are not reported as set bits.
+    ///
+    /// ```
+    /// # use bit_test::{count_ones, Bitmap};
+    /// let mut bitmap = Bitmap::from_indices(13, [2, 7, 12]);
+    /// bitmap.complement();
+    /// assert_eq!(count_ones(bitmap.as_bytes()), 10);
+    /// assert_eq!(bitmap.iter_ones().count(), 10);
+    /// assert_eq!(bitmap.to_string(), "1101111011110");
+    /// ```
+    pub fn complement(&mut self) {
+        complement(&mut self.bytes);
+        self.clear_padding();
+    }
+
+    /// Create a bitmap of `bit_len` bits from its bytes, without copying them
+    ///
+    /// There must be exactly enough bytes to hold `bit_len` bits, and the
+    /// padding bits of the last byte beyond `bit_len` must be clear.
+    ///
+    /// ```
+    /// # use bit_test::{Bitmap, FromBytesError};
+    /// let bitmap = Bitmap::from_bytes(vec![0b1010_0101, 0b10], 10).unwrap();
+    /// assert_eq!(bitmap.to_string(), "1010010101");
+    /// assert_eq!(bitmap.as_bytes(), [0b1010_0101, 0b10]);
+    /// assert_eq!(bitmap.into_bytes(), [0b1010_0101, 0b10]);
+    ///
+    /// assert_eq!(
+    ///     Bitmap::from_bytes(vec![0; 2], 17),
+    ///     Err(FromBytesError::LengthMismatch { len: 2, expected: 3 })
+    /// );
+    /// assert_eq!(
+    ///     Bitmap::from_bytes(vec![0, 0b100], 10),
+    ///     Err(FromBytesError::PaddingBitSet { idx: 10 })
+    /// );
+    /// ```
+    pub fn from_bytes(bytes: Vec<u8>, bit_len: usize) -> Result<Self, FromBytesError> {
+        let expected = bit_len.div_ceil(8);
+        if bytes.len() != expected {
+            return Err(FromBytesError::LengthMismatch {
+                len: bytes.len(),
+                expected,
+            });
+        }
+        if let Some(idx) = find_next_set(&bytes, bit_len) {
+            return Err(FromBytesError::PaddingBitSet { idx });
+        }
+        Ok(Self { bytes, bit_len })
+    }
+
+    /// Like [`from_bytes()`](Self::from_bytes), but does not check that the
+    /// padding bits of the last byte are clear
+    ///
+    /// This is meant for bytes which are already known to be valid. If a
+    /// padding bit is set anyway, operations which scan the bits up to `len()`
+    /// such as [`iter_ones()`](Self::iter_ones) still ignore it, but the
+    /// result of other operations, such as equality, [`push()`](Self::push)
+    /// or [`resize()`](Self::resize), is unspecified.
+    ///
+    /// ```
+    /// # use bit_test::Bitmap;
+    /// // Bit 0 is set, and so are all padding bits of the last byte
+    /// let bitmap = Bitmap::from_bytes_unvalidated(vec![0b0000_0001, 0b1111_1100], 10);
+    /// assert_eq!(bitmap.iter_ones().collect::<Vec<_>>(), [0]);
+    /// assert_eq!(bitmap.iter_ones().rev().collect::<Vec<_>>(), [0]);
+    /// assert_eq!(bitmap.iter().filter(|&bit| bit).count(), 1);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If there are not exactly enough bytes to hold `bit_len` bits.
+    pub fn from_bytes_unvalidated(bytes: Vec<u8>, bit_len: usize) -> Self {
+        assert_eq!(
+            bytes.len(),
+            bit_len.div_ceil(8),
+            "bitmap of {bit_len} bits should have {} bytes",
+            bit_len.div_ceil(8)
+        );
+        Self { bytes, bit_len }
+    }
+
+    /// Bytes of the bitmap, where padding bits beyond `len()` are clear
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Extract the bytes of the bitmap, where padding bits beyond `len()` are
+    /// clear
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Borrow the bits of the bitmap as a [`BitSlice`]
+    ///
+    /// ```
+    /// # use bit_test::Bitmap;
+    /// let bitmap = Bitmap::from_indices(10, [3, 9]);
+    /// let bits = bitmap.as_slice();
+    /// assert_eq!(bits.len(), 10);
+    /// assert_eq!(bits.iter_ones().collect::<Vec<_>>(), [3, 9]);
+    /// ```
+    #[inline]
+    pub fn as_slice(&self) -> BitSlice<'_> {
+        BitSlice {
+            bytes: &self.bytes,
+            bit_offset: 0,
+            bit_len: self.bit_len,
+        }
+    }
+
+    /// Split the bitmap into two borrowed slices, holding its first `mid` bits
+    /// and the remaining bits, see [`BitSlice::split_at_bit()`]
+    ///
+    /// ```
+    /// # use bit_test::Bitmap;
+    /// let bitmap = Bitmap::from_indices(20, [1, 9, 17]);
+    /// for mid in [8, 11] {
+    ///     let (head, tail) = bitmap.split_at_bit(mid);
+    ///     assert_eq!((head.len(), tail.len()), (mid, 20 - mid));
+    ///     let ones = head.iter_ones().chain(tail.iter_ones().map(|idx| mid + idx));
+    ///     assert_eq!(ones.collect::<Vec<_>>(), [1, 9, 17]);
+    /// }
+    /// ```
+    #[inline]
+    pub fn split_at_bit(&self, mid: usize) -> (BitSlice<'_>, BitSlice<'_>) {
+        self.as_slice().split_at_bit(mid)
+    }
+
+    /// Mutably borrow the bits of the bitmap as a [`BitSliceMut`]
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> BitSliceMut<'_> {
+        BitSliceMut {
+            bytes: &mut self.bytes,
+            bit_len: self.bit_len,
+        }
+    }
+
+    /// Clear the bits of the last byte which are beyond `bit_len`
+    #[inline]
+    fn clear_padding(&mut self) {
+        let used_bits = self.bit_len & 7;
+        if used_bits != 0 {
+            if let Some(last) = self.bytes.last_mut() {
+                *last &= (1 << used_bits) - 1;
+            }
+        }
+    }
+
+    /// Panic if `idx` is not a valid bit index, even if the byte that would
+    /// contain it exists in the backing storage
+    #[inline]
+    fn check_index(&self, idx: usize) {
+        assert!(
+            idx < self.bit_len,
+            "bit index {idx} is out of bounds for a bitmap of {} bits",
+            self.bit_len
+        );
+    }
+}
+
+/// Error returned when the bytes given to [`Bitmap::from_bytes()`] are not
+/// a valid bitmap of the requested length
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FromBytesError {
+    /// The number of bytes does not match the requested number of bits
+    LengthMismatch { len: usize, expected: usize },
+
+    /// A padding bit beyond the requested number of bits is set
+    PaddingBitSet { idx: usize },
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for FromBytesError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::LengthMismatch { len, expected } => {
+                write!(f, "expected {expected} bytes, got {len}")
+            }
+            Self::PaddingBitSet { idx } => {
+                write!(f, "padding bit {idx} should be clear")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::error::Error for FromBytesError {}
+
+/// Number of bits after which the [`Debug`](core::fmt::Debug) output of a
+/// [`Bitmap`] is truncated
+#[cfg(feature = "std")]
+const DEBUG_MAX_BITS: usize = 64;
+
+/// Show the length and the first bits of a bitmap, in index order and grouped
+/// by bytes
+///
+/// ```
+/// # use bit_test::Bitmap;
+/// let bits = |s: &str| s.chars().map(|c| c == '1').collect::<Bitmap>();
+/// assert_eq!(
+///     format!("{:?}", bits("1100000001")),
+///     "Bitmap { len: 10, bits: 11000000_01 }"
+/// );
+/// assert_eq!(
+///     format!("{:?}", Bitmap::with_bits(100)),
+///     "Bitmap { len: 100, bits: 00000000_00000000_00000000_00000000_\
+///      00000000_00000000_00000000_00000000_... }"
+/// );
+/// ```
+#[cfg(feature = "std")]
+impl core::fmt::Debug for Bitmap {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        /// Adapter which displays the bits without quotes
+        struct DebugBits<'a>(&'a Bitmap);
+        impl core::fmt::Debug for DebugBits<'_> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                use core::fmt::Write;
+                for (idx, bit) in self.0.iter().take(DEBUG_MAX_BITS).enumerate() {
+                    if idx != 0 && idx.is_multiple_of(8) {
+                        f.write_char('_')?;
+                    }
+                    f.write_char(if bit { '1' } else { '0' })?;
+                }
+                if self.0.len() > DEBUG_MAX_BITS {
+                    f.write_str("_...")?;
+                }
+                Ok(())
+            }
+        }
+        f.debug_struct("Bitmap")
+            .field("len", &self.bit_len)
+            .field("bits", &DebugBits(self))
+            .finish()
+    }
+}
+
+/// Show all bits of a bitmap as '0' and '1' characters in index order, like
+/// [`to_binary_string()`] but without padding bits
+///
+/// ```
+/// # use bit_test::Bitmap;
+/// let bitmap = [true, true, false, true].into_iter().collect::<Bitmap>();
+/// assert_eq!(bitmap.to_string(), "1101");
+/// ```
+#[cfg(feature = "std")]
+impl core::fmt::Display for Bitmap {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use core::fmt::Write;
+        for bit in self {
+            f.write_char(if bit { '1' } else { '0' })?;
+        }
+        Ok(())
+    }
+}
+
+/// Build a bitmap from a sequence of bits
+///
+/// ```
+/// # use bit_test::Bitmap;
+/// let bm: Bitmap = [true, false, true].iter().copied().collect();
+/// assert_eq!(bm.len(), 3);
+/// assert!(bm.test(0) && !bm.test(1) && bm.test(2));
+/// ```
+#[cfg(feature = "std")]
+impl FromIterator<bool> for Bitmap {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut bitmap = Self {
+            bytes: Vec::with_capacity(iter.size_hint().0.div_ceil(8)),
+            bit_len: 0,
+        };
+        for bit in iter {
+            bitmap.push(bit);
+        }
+        bitmap
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<&[bool]> for Bitmap {
+    fn from(bits: &[bool]) -> Self {
+        bits.iter().copied().collect()
+    }
+}
+
+/// ```
+/// # use bit_test::Bitmap;
+/// let bm = Bitmap::from(&[true, false, true][..]);
+/// assert_eq!(bm.into_iter().collect::<Vec<_>>(), [true, false, true]);
+/// ```
+#[cfg(feature = "std")]
+impl<'a> IntoIterator for &'a Bitmap {
+    type Item = bool;
+    type IntoIter = Bits<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Bits<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over all bits of a [`Bitmap`], including clear ones
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct Bits<'a> {
+    bytes: &'a [u8],
+
+    /// Start of the range of bit indices that remain to be yielded
+    front: usize,
+
+    /// End of the range of bit indices that remain to be yielded
+    back: usize,
+}
+
+#[cfg(feature = "std")]
+impl Iterator for Bits<'_> {
+    type Item = bool;
+
+    #[inline]
+    fn next(&mut self) -> Option<bool> {
+        if self.front >= self.back {
+            return None;
+        }
+        let bit = bit_test_const_table(self.bytes, self.front);
+        self.front += 1;
+        Some(bit)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+#[cfg(feature = "std")]
+impl DoubleEndedIterator for Bits<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<bool> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(bit_test_const_table(self.bytes, self.back))
+    }
+}
+
+#[cfg(feature = "std")]
+impl ExactSizeIterator for Bits<'_> {}
+
+#[cfg(feature = "std")]
+impl core::iter::FusedIterator for Bits<'_> {}
+
+/// Growable vector of booleans, which stores 8 of them per byte unlike
+/// `Vec<bool>`
+///
+/// The API mimics that of `Vec<bool>` where possible. Since individual bits
+/// cannot be borrowed, it does not implement `Index`: elements are read with
+/// [`get()`](Self::get) and written with [`set()`](Self::set) instead.
+///
+/// ```
+/// # use bit_test::BoolVec;
+/// let mut flags = BoolVec::new();
+/// flags.push(true);
+/// flags.push(false);
+/// flags.push(true);
+/// assert_eq!(flags.len(), 3);
+/// assert_eq!(flags.get(1), Some(false));
+/// assert_eq!(flags.get(3), None);
+/// flags.set(1, true);
+/// flags.set(2, false);
+/// assert_eq!(flags.iter().collect::<Vec<_>>(), [true, true, false]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct BoolVec {
+    bits: Bitmap,
+}
+
+#[cfg(feature = "std")]
+impl BoolVec {
+    /// Create an empty vector
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of booleans in the vector
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Truth that the vector contains no booleans
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// Append a boolean at the end of the vector
+    #[inline]
+    pub fn push(&mut self, value: bool) {
+        self.bits.push(value)
+    }
+
+    /// Remove the last boolean of the vector and return it, if any
+    #[inline]
+    pub fn pop(&mut self) -> Option<bool> {
+        self.bits.pop()
+    }
+
+    /// Read the boolean at index `idx`, if it is within the vector
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<bool> {
+        (idx < self.len()).then(|| bit_test_const_table(&self.bits.bytes, idx))
+    }
+
+    /// Overwrite the boolean at index `idx`
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is out of bounds, like indexing a `Vec<bool>`.
+    #[inline]
+    pub fn set(&mut self, idx: usize, value: bool) {
+        self.bits.check_index(idx);
+        bit_assign_const_table(&mut self.bits.bytes, idx, value)
+    }
+
+    /// Iterate over the booleans of the vector, in order
+    #[inline]
+    pub fn iter(&self) -> Bits<'_> {
+        self.bits.iter()
+    }
+
+    /// Access the underlying bitmap, e.g. to use the bulk bitmap operations
+    #[inline]
+    pub fn as_bitmap(&self) -> &Bitmap {
+        &self.bits
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Bitmap> for BoolVec {
+    #[inline]
+    fn from(bits: Bitmap) -> Self {
+        Self { bits }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<BoolVec> for Bitmap {
+    #[inline]
+    fn from(vec: BoolVec) -> Self {
+        vec.bits
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromIterator<bool> for BoolVec {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        Self {
+            bits: Bitmap::from_iter(iter),
+        }
+    }
+}
+
+/// Borrowed view of the first `len()` bits of a byte slice
+///
+/// Bits beyond `len()` are ignored, even if they are set, which lets callers
+/// work on a part of a larger bitmap without copying it. Slices produced by
+/// [`split_at_bit()`](Self::split_at_bit) may also start in the middle of a
+/// byte.
+///
+/// ```
+/// # use bit_test::BitSlice;
+/// let bytes = [0b0000_0101, 0b1111_0000];
+/// let bits = BitSlice::new(&bytes, 12);
+/// assert_eq!(bits.len(), 12);
+/// assert!(bits.test(2) && !bits.test(3));
+/// assert_eq!(bits.count_ones(), 2);
+/// assert_eq!(bits.iter_ones().collect::<Vec<_>>(), [0, 2]);
+///
+/// let all_bits = BitSlice::from(&bytes[..]);
+/// assert_eq!(all_bits.iter_ones().collect::<Vec<_>>(), [0, 2, 12, 13, 14, 15]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct BitSlice<'a> {
+    bytes: &'a [u8],
+
+    /// Index of the first bit of the slice within `bytes`, which is smaller
+    /// than 8
+    bit_offset: usize,
+
+    bit_len: usize,
+}
+
+impl<'a> BitSlice<'a> {
+    /// View the first `bit_len` bits of `bytes`
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` holds less than `bit_len` bits.
+    #[inline]
+    pub fn new(bytes: &'a [u8], bit_len: usize) -> Self {
+        check_bit_len(bytes, bit_len);
+        Self {
+            bytes,
+            bit_offset: 0,
+            bit_len,
+        }
+    }
+
+    /// Number of bits in the slice
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bit_len
+    }
+
+    /// Truth that the slice contains no bits
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bit_len == 0
+    }
+
+    #[inline]
+    pub fn test(&self, idx: usize) -> bool {
+        check_slice_index(idx, self.bit_len);
+        bit_test_const_table(self.bytes, self.bit_offset + idx)
+    }
+
+    /// Number of set bits in the slice
+    #[inline]
+    pub fn count_ones(&self) -> usize {
+        count_ones_in_range(self.bytes, self.bit_offset, self.bit_offset + self.bit_len)
+    }
+
+    /// Iterate over the indices of the set bits of the slice, in ascending
+    /// order
+    #[inline]
+    pub fn iter_ones(&self) -> SliceOnes<'a> {
+        SliceOnes {
+            set_bits: SetBits {
+                bitmap: self.bytes,
+                front: self.bit_offset,
+                back: self.bit_offset + self.bit_len,
+            },
+            bit_offset: self.bit_offset,
+        }
+    }
+
+    /// Split the slice into its first `mid` bits and the remaining bits
+    ///
+    /// If `mid` is not a multiple of 8, the second slice starts in the middle
+    /// of a byte.
+    ///
+    /// # Panics
+    ///
+    /// If `mid` is greater than `len()`.
+    ///
+    /// ```
+    /// # use bit_test::BitSlice;
+    /// let bytes = [0b1000_0001, 0b0000_0110];
+    /// let bits = BitSlice::new(&bytes, 12);
+    ///
+    /// let (head, tail) = bits.split_at_bit(8);
+    /// assert_eq!(head.iter_ones().collect::<Vec<_>>(), [0, 7]);
+    /// assert_eq!(tail.iter_ones().collect::<Vec<_>>(), [1, 2]);
+    /// assert_eq!(tail.len(), 4);
+    ///
+    /// let (head, tail) = bits.split_at_bit(5);
+    /// assert_eq!(head.iter_ones().collect::<Vec<_>>(), [0]);
+    /// assert_eq!(tail.iter_ones().collect::<Vec<_>>(), [2, 4, 5]);
+    /// assert!(tail.test(2) && !tail.test(3));
+    /// assert_eq!((head.len(), tail.len()), (5, 7));
+    /// assert_eq!((head.count_ones(), tail.count_ones()), (1, 3));
+    ///
+    /// let (head, tail) = tail.split_at_bit(3);
+    /// assert_eq!(head.iter_ones().collect::<Vec<_>>(), [2]);
+    /// assert_eq!(tail.iter_ones().rev().collect::<Vec<_>>(), [2, 1]);
+    /// ```
+    #[inline]
+    pub fn split_at_bit(&self, mid: usize) -> (BitSlice<'a>, BitSlice<'a>) {
+        assert!(
+            mid <= self.bit_len,
+            "can't split a bit slice of {} bits at bit {mid}",
+            self.bit_len
+        );
+        let split = self.bit_offset + mid;
+        let head = Self {
+            bytes: &self.bytes[..split.div_ceil(8)],
+            bit_offset: self.bit_offset,
+            bit_len: mid,
+        };
+        let tail = Self {
+            bytes: &self.bytes[split >> 3..],
+            bit_offset: split & 7,
+            bit_len: self.bit_len - mid,
+        };
+        (head, tail)
+    }
+}
+
+/// View all bits of a byte slice
+impl<'a> From<&'a [u8]> for BitSlice<'a> {
+    #[inline]
+    fn from(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            bit_offset: 0,
+            bit_len: bytes.len() * 8,
+        }
+    }
+}
+
+/// Iterator over the indices of the set bits of a [`BitSlice`]
+#[derive(Clone, Debug)]
+pub struct SliceOnes<'a> {
+    /// Set bits of the underlying bytes, indexed from the start of the bytes
+    set_bits: SetBits<'a>,
+
+    /// Index of the first bit of the slice within the underlying bytes
+    bit_offset: usize,
+}
+
+impl Iterator for SliceOnes<'_> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        self.set_bits.next().map(|idx| idx - self.bit_offset)
+    }
+}
+
+impl DoubleEndedIterator for SliceOnes<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        self.set_bits.next_back().map(|idx| idx - self.bit_offset)
+    }
+}
+
+impl core::iter::FusedIterator for SliceOnes<'_> {}
+
+/// Mutable counterpart of [`BitSlice`]
+///
+/// Bits beyond `len()` are never modified.
+///
+/// ```
+/// # use bit_test::BitSliceMut;
+/// let mut bytes = [0u8; 2];
+/// let mut bits = BitSliceMut::new(&mut bytes, 12);
+/// bits.set(1);
+/// bits.set_range(4, 12);
+/// bits.clear(5);
+/// bits.toggle_range(0, 2);
+/// assert_eq!(bits.as_bit_slice().iter_ones().collect::<Vec<_>>(), [0, 4, 6, 7, 8, 9, 10, 11]);
+/// assert_eq!(bytes, [0b1101_0001, 0b0000_1111]);
+/// ```
+#[derive(Debug)]
+pub struct BitSliceMut<'a> {
+    bytes: &'a mut [u8],
+    bit_len: usize,
+}
+
+impl<'a> BitSliceMut<'a> {
+    /// View the first `bit_len` bits of `bytes`
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` holds less than `bit_len` bits.
+    #[inline]
+    pub fn new(bytes: &'a mut [u8], bit_len: usize) -> Self {
+        check_bit_len(bytes, bit_len);
+        Self { bytes, bit_len }
+    }
+
+    /// Read-only view of the same bits
+    #[inline]
+    pub fn as_bit_slice(&self) -> BitSlice<'_> {
+        BitSlice {
+            bytes: self.bytes,
+            bit_offset: 0,
+            bit_len: self.bit_len,
+        }
+    }
+
+    /// Number of bits in the slice
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bit_len
+    }
+
+    /// Truth that the slice contains no bits
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bit_len == 0
+    }
+
+    #[inline]
+    pub fn test(&self, idx: usize) -> bool {
+        self.as_bit_slice().test(idx)
+    }
+
+    #[inline]
+    pub fn set(&mut self, idx: usize) {
+        check_slice_index(idx, self.bit_len);
+        bit_set_const_table(self.bytes, idx)
+    }
+
+    #[inline]
+    pub fn clear(&mut self, idx: usize) {
+        check_slice_index(idx, self.bit_len);
+        bit_clear_const_table(self.bytes, idx)
+    }
+
+    /// Like [`set_range()`], but panics if `end` is beyond `len()`
+    #[inline]
+    pub fn set_range(&mut self, start: usize, end: usize) {
+        check_slice_range_end(end, self.bit_len);
+        set_range(self.bytes, start, end)
+    }
+
+    /// Like [`clear_range()`], but panics if `end` is beyond `len()`
+    #[inline]
+    pub fn clear_range(&mut self, start: usize, end: usize) {
+        check_slice_range_end(end, self.bit_len);
+        clear_range(self.bytes, start, end)
+    }
+
+    /// Like [`toggle_range()`], but panics if `end` is beyond `len()`
+    #[inline]
+    pub fn toggle_range(&mut self, start: usize, end: usize) {
+        check_slice_range_end(end, self.bit_len);
+        toggle_range(self.bytes, start, end)
+    }
+}
+
+/// View all bits of a byte slice
+impl<'a> From<&'a mut [u8]> for BitSliceMut<'a> {
+    #[inline]
+    fn from(bytes: &'a mut [u8]) -> Self {
+        let bit_len = bytes.len() * 8;
+        Self { bytes, bit_len }
+    }
+}
+
+/// Panic if `bytes` holds less than `bit_len` bits
+#[inline]
+fn check_bit_len(bytes: &[u8], bit_len: usize) {
+    assert!(
+        bit_len.div_ceil(8) <= bytes.len(),
+        "{} bytes can't hold {bit_len} bits",
+        bytes.len()
+    );
+}
+
+/// Panic if `idx` is not a valid bit index for a bit slice of `bit_len` bits
+#[inline]
+fn check_slice_index(idx: usize, bit_len: usize) {
+    assert!(
+        idx < bit_len,
+        "bit index {idx} is out of bounds for a bit slice of {bit_len} bits"
+    );
+}
+
+/// Panic if a bit range which ends at `end` goes beyond the end of a bit slice
+/// of `bit_len` bits
+#[inline]
+fn check_slice_range_end(end: usize, bit_len: usize) {
+    assert!(
+        end <= bit_len,
+        "bit range ends at {end}, beyond the end of a bit slice of {bit_len} bits"
+    );
+}
+
+#[inline]
+pub fn bit_test_u64(words: &[u64], idx: usize) -> bool {
+    words[idx >> 6] & (1 << (idx & 63)) != 0
+}
+
+#[inline]
+pub fn bit_set_u64(words: &mut [u64], idx: usize) {
+    words[idx >> 6] |= 1 << (idx & 63)
+}
+
+#[inline]
+pub fn bit_clear_u64(words: &mut [u64], idx: usize) {
+    words[idx >> 6] &= !(1 << (idx & 63))
+}
+
+/// Shift which turns a bit index into an index of a `usize` word
+const WORD_SHIFT: u32 = usize::BITS.trailing_zeros();
+
+/// Mask which turns a bit index into a bit index within a `usize` word
+const WORD_MASK: usize = usize::BITS as usize - 1;
+
+/// Like [`bit_test_u64()`], but operates on words of the platform's native
+/// width, i.e. 32-bit words on 32-bit targets and 64-bit words on 64-bit ones
+#[inline]
+pub fn bit_test_word(words: &[usize], idx: usize) -> bool {
+    words[idx >> WORD_SHIFT] & (1 << (idx & WORD_MASK)) != 0
+}
+
+#[inline]
+pub fn bit_set_word(words: &mut [usize], idx: usize) {
+    words[idx >> WORD_SHIFT] |= 1 << (idx & WORD_MASK)
+}
+
+#[inline]
+pub fn bit_clear_word(words: &mut [usize], idx: usize) {
+    words[idx >> WORD_SHIFT] &= !(1 << (idx & WORD_MASK))
+}
+
+#[inline]
+pub fn count_ones(bitmap: &[u8]) -> usize {
+    bitmap.iter().map(|byte| byte.count_ones() as usize).sum()
+}
+
+#[inline]
+pub fn count_zeros(bitmap: &[u8]) -> usize {
+    bitmap.len() * 8 - count_ones(bitmap)
+}
+
+/// Number of set bits of each possible byte value
+pub const POPCOUNT_TABLE: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        // Each byte has one more bit set than the byte where its lowest set
+        // bit is cleared, which has a lower value and was already computed
+        if byte > 0 {
+            table[byte] = table[byte & (byte - 1)] + 1;
+        }
+        byte += 1;
+    }
+    table
+};
+
+const _: () = {
+    let mut byte = 0;
+    while byte < 256 {
+        assert!(POPCOUNT_TABLE[byte] as u32 == (byte as u8).count_ones());
+        byte += 1;
+    }
+};
+
+/// Like [`count_ones()`], but looks up the population count of each byte in
+/// [`POPCOUNT_TABLE`] instead of using the hardware population count
+#[inline]
+pub fn count_ones_table(bitmap: &[u8]) -> usize {
+    bitmap
+        .iter()
+        .map(|&byte| POPCOUNT_TABLE[byte as usize] as usize)
+        .sum()
+}
+
+#[inline]
+pub fn count_ones_u64(bitmap: &[u8]) -> usize {
+    let words = bitmap.chunks_exact(8);
+    let tail = count_ones(words.remainder());
+    words
+        .map(|word| u64::from_ne_bytes(word.try_into().unwrap()).count_ones() as usize)
+        .sum::<usize>()
+        + tail
+}
+
+/// Like [`count_ones()`], but splits the bitmap into chunks which are counted
+/// in parallel by the rayon thread pool
+///
+/// Spreading the work across threads has a fixed cost, so this is slower than
+/// [`count_ones()`] for bitmaps which are smaller than a few hundred KiB. The
+/// `popcount_parallel` benchmark shows where the crossover lies on a given
+/// machine.
+///
+/// ```
+/// # use bit_test::{count_ones, count_ones_parallel};
+/// let bitmap = vec![0b1011_0001; 1 << 20];
+/// assert_eq!(count_ones_parallel(&bitmap), count_ones(&bitmap));
+/// ```
+#[cfg(feature = "rayon")]
+#[inline]
+pub fn count_ones_parallel(bitmap: &[u8]) -> usize {
+    use rayon::prelude::*;
+    // Large enough to amortize the overhead of scheduling a chunk, small
+    // enough to keep all threads busy on multi-megabyte bitmaps
+    const CHUNK_BYTES: usize = 64 * 1024;
+    bitmap.par_chunks(CHUNK_BYTES).map(count_ones).sum()
+}
+
+#[cfg(feature = "portable_simd")]
+#[inline]
+pub fn count_ones_simd(bitmap: &[u8]) -> usize {
+    use core::simd::{num::SimdUint, Simd};
+    const LANES: usize = 32;
+    // Each chunk adds at most 8 to each u8 lane, so per-lane accumulators must
+    // be flushed every 31 chunks to avoid overflow
+    const CHUNKS_PER_FLUSH: usize = u8::MAX as usize / 8;
+    let (chunks, tail) = bitmap.as_chunks::<LANES>();
+    let mut total = 0;
+    for block in chunks.chunks(CHUNKS_PER_FLUSH) {
+        let mut acc = Simd::<u8, LANES>::splat(0);
+        for chunk in block {
+            acc += Simd::from_array(*chunk).count_ones();
+        }
+        total += acc.cast::<u16>().reduce_sum() as usize;
+    }
+    total + count_ones(tail)
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+#[inline]
+pub fn count_ones_neon(bitmap: &[u8]) -> usize {
+    use core::arch::aarch64::{vaddlvq_u8, vaddq_u8, vcntq_u8, vdupq_n_u8, vld1q_u8};
+    // Same overflow concern as in count_ones_simd
+    const CHUNKS_PER_FLUSH: usize = u8::MAX as usize / 8;
+    let (chunks, tail) = bitmap.as_chunks::<16>();
+    let mut total = 0;
+    for block in chunks.chunks(CHUNKS_PER_FLUSH) {
+        // SAFETY: NEON support was checked at compile time and each chunk is
+        //         16 bytes long
+        unsafe {
+            let mut acc = vdupq_n_u8(0);
+            for chunk in block {
+                acc = vaddq_u8(acc, vcntq_u8(vld1q_u8(chunk.as_ptr())));
+            }
+            total += vaddlvq_u8(acc) as usize;
+        }
+    }
+    total + count_ones(tail)
+}
+
+#[inline]
+pub fn find_first_set(bitmap: &[u8]) -> Option<usize> {
+    let byte_idx = bitmap.iter().position(|&byte| byte != 0)?;
+    Some(byte_idx * 8 + bitmap[byte_idx].trailing_zeros() as usize)
+}
+
+#[inline]
+pub fn find_first_clear(bitmap: &[u8]) -> Option<usize> {
+    let byte_idx = bitmap.iter().position(|&byte| byte != u8::MAX)?;
+    Some(byte_idx * 8 + bitmap[byte_idx].trailing_ones() as usize)
+}
+
+#[cfg(feature = "portable_simd")]
+#[inline]
+pub fn find_first_set_simd(bitmap: &[u8]) -> Option<usize> {
+    use core::simd::{num::SimdUint, Simd};
+    const LANES: usize = 32;
+    let (chunks, tail) = bitmap.as_chunks::<LANES>();
+    let Some(chunk_idx) = chunks
+        .iter()
+        .position(|chunk| Simd::from_array(*chunk).reduce_or() != 0)
+    else {
+        let tail_start = chunks.len() * LANES;
+        return find_first_set(tail).map(|idx| tail_start * 8 + idx);
+    };
+    let chunk_start = chunk_idx * LANES;
+    find_first_set(&chunks[chunk_idx]).map(|idx| chunk_start * 8 + idx)
+}
+
+#[inline]
+pub fn find_next_set(bitmap: &[u8], from: usize) -> Option<usize> {
+    let first_byte = from >> 3;
+    let head = *bitmap.get(first_byte)? & !((1u8 << (from & 7)) - 1);
+    if head != 0 {
+        return Some(first_byte * 8 + head.trailing_zeros() as usize);
+    }
+    let next_byte = first_byte + 1;
+    find_first_set(&bitmap[next_byte..]).map(|idx| next_byte * 8 + idx)
+}
+
+#[inline]
+pub fn find_last_set(bitmap: &[u8]) -> Option<usize> {
+    let byte_idx = bitmap.iter().rposition(|&byte| byte != 0)?;
+    Some(byte_idx * 8 + 7 - bitmap[byte_idx].leading_zeros() as usize)
+}
+
+#[inline]
+pub fn find_last_clear(bitmap: &[u8]) -> Option<usize> {
+    let byte_idx = bitmap.iter().rposition(|&byte| byte != u8::MAX)?;
+    Some(byte_idx * 8 + 7 - (!bitmap[byte_idx]).leading_zeros() as usize)
+}
+
+/// Find the last set bit at an index below `end`
+#[inline]
+fn find_prev_set(bitmap: &[u8], end: usize) -> Option<usize> {
+    let last = end.checked_sub(1)?;
+    let last_byte = last >> 3;
+    let head = bitmap[last_byte] & (u8::MAX >> (7 - (last & 7)));
+    if head != 0 {
+        return Some(last_byte * 8 + 7 - head.leading_zeros() as usize);
+    }
+    find_last_set(&bitmap[..last_byte])
+}
+
+/// Iterate over the indices of the set bits of a bitmap, in ascending order
+#[inline]
+pub fn set_bits(bitmap: &[u8]) -> SetBits<'_> {
+    SetBits {
+        bitmap,
+        front: 0,
+        back: bitmap.len() * 8,
+    }
+}
+
+/// Iterate over the indices of the set bits of a bitmap, starting at bit
+/// `start` and wrapping around to bit 0 after the end of the bitmap
+///
+/// Each set bit is yielded once, which lets round-robin schedulers resume
+/// their scan where the previous one left off. Start indices past the end of
+/// the bitmap are wrapped around too.
+///
+/// ```
+/// # use bit_test::set_bits_from;
+/// let bitmap = [0b0010_0001, 0b1000_0100];
+/// assert_eq!(set_bits_from(&bitmap, 6).collect::<Vec<_>>(), [10, 15, 0, 5]);
+/// ```
+#[inline]
+pub fn set_bits_from(bitmap: &[u8], start: usize) -> core::iter::Chain<SetBits<'_>, SetBits<'_>> {
+    let bits = bitmap.len() * 8;
+    let start = start.checked_rem(bits).unwrap_or(0);
+    let back_half = SetBits {
+        bitmap,
+        front: start,
+        back: bits,
+    };
+    let front_half = SetBits {
+        bitmap,
+        front: 0,
+        back: start,
+    };
+    back_half.chain(front_half)
+}
+
+/// Iterator over the indices of the set bits of a bitmap
+#[derive(Clone, Debug)]
+pub struct SetBits<'a> {
+    bitmap: &'a [u8],
+
+    /// Start of the range of bit indices that remain to be scanned
+    front: usize,
+
+    /// End of the range of bit indices that remain to be scanned
+    back: usize,
+}
+
+impl Iterator for SetBits<'_> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        if self.front >= self.back {
+            return None;
+        }
+        match find_next_set(self.bitmap, self.front) {
+            Some(idx) if idx < self.back => {
+                self.front = idx + 1;
+                Some(idx)
+            }
+            _ => {
+                self.front = self.back;
+                None
+            }
+        }
+    }
+}
+
+impl DoubleEndedIterator for SetBits<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        if self.front >= self.back {
+            return None;
+        }
+        match find_prev_set(self.bitmap, self.back) {
+            Some(idx) if idx >= self.front => {
+                self.back = idx;
+                Some(idx)
+            }
+            _ => {
+                self.back = self.front;
+                None
+            }
+        }
+    }
+}
+
+impl core::iter::FusedIterator for SetBits<'_> {}
+
+/// List the indices of the set bits of a bitmap, in ascending order
+///
+/// This sparse representation takes less memory than the bitmap itself when
+/// less than 1 bit in 32 is set.
+///
+/// ```
+/// # use bit_test::{from_sparse, to_sparse};
+/// let bitmap = [0b0010_0001, 0, 0b1000_0000];
+/// let sparse = to_sparse(&bitmap);
+/// assert_eq!(sparse, [0, 5, 23]);
+/// assert_eq!(from_sparse(&sparse, 24), bitmap);
+/// ```
+#[cfg(feature = "std")]
+pub fn to_sparse(bitmap: &[u8]) -> Vec<u32> {
+    set_bits(bitmap)
+        .map(|idx| u32::try_from(idx).expect("bit index does not fit in u32"))
+        .collect()
+}
+
+/// Build a bitmap of `bits` bits where only the bits at `indices` are set,
+/// as listed by [`to_sparse()`]
+///
+/// Indices don't need to be sorted, and duplicate indices simply set the same
+/// bit again. Panics if an index is out of bounds, so that padding bits of the
+/// last byte are always clear.
+///
+/// ```
+/// # use bit_test::from_sparse;
+/// assert_eq!(from_sparse(&[9, 2, 9], 12), [0b0000_0100, 0b0000_0010]);
+/// ```
+#[cfg(feature = "std")]
+pub fn from_sparse(indices: &[u32], bits: usize) -> Vec<u8> {
+    let mut bitmap = vec![0; bits.div_ceil(8)];
+    for &idx in indices {
+        let idx = index_to_usize(idx);
+        assert!(
+            idx < bits,
+            "bit index {idx} is out of bounds for a bitmap of {bits} bits"
+        );
+        bit_set_const_table(&mut bitmap, idx);
+    }
+    bitmap
+}
+
+#[inline]
+pub fn set_range(bitmap: &mut [u8], start: usize, end: usize) {
+    let RangeBytes { head, middle, tail } = RangeBytes::new(start, end);
+    if let Some((byte, mask)) = head {
+        bitmap[byte] |= mask;
+    }
+    bitmap[middle].fill(u8::MAX);
+    if let Some((byte, mask)) = tail {
+        bitmap[byte] |= mask;
+    }
+}
+
+#[inline]
+pub fn clear_range(bitmap: &mut [u8], start: usize, end: usize) {
+    let RangeBytes { head, middle, tail } = RangeBytes::new(start, end);
+    if let Some((byte, mask)) = head {
+        bitmap[byte] &= !mask;
+    }
+    bitmap[middle].fill(0);
+    if let Some((byte, mask)) = tail {
+        bitmap[byte] &= !mask;
+    }
+}
+
+#[inline]
+pub fn toggle_range(bitmap: &mut [u8], start: usize, end: usize) {
+    let RangeBytes { head, middle, tail } = RangeBytes::new(start, end);
+    if let Some((byte, mask)) = head {
+        bitmap[byte] ^= mask;
+    }
+    for byte in &mut bitmap[middle] {
+        *byte ^= u8::MAX;
+    }
+    if let Some((byte, mask)) = tail {
+        bitmap[byte] ^= mask;
+    }
+}
+
+#[inline]
+pub fn test_range_all(bitmap: &[u8], start: usize, end: usize) -> bool {
+    let RangeBytes { head, middle, tail } = RangeBytes::new(start, end);
+    let partial_all = |(byte, mask): (usize, u8)| bitmap[byte] & mask == mask;
+    head.is_none_or(partial_all)
+        && bitmap[middle].iter().all(|&byte| byte == u8::MAX)
+        && tail.is_none_or(partial_all)
+}
+
+#[inline]
+pub fn test_range_any(bitmap: &[u8], start: usize, end: usize) -> bool {
+    let RangeBytes { head, middle, tail } = RangeBytes::new(start, end);
+    let partial_any = |(byte, mask): (usize, u8)| bitmap[byte] & mask != 0;
+    head.is_some_and(partial_any)
+        || bitmap[middle].iter().any(|&byte| byte != 0)
+        || tail.is_some_and(partial_any)
+}
+
+/// Like [`set_range()`], but returns an error instead of panicking if the
+/// range is reversed or extends past the end of the bitmap
+///
+/// ```
+/// # use bit_test::{set_range_checked, RangeError};
+/// let mut bitmap = [0; 2];
+/// assert_eq!(set_range_checked(&mut bitmap, 3, 12), Ok(()));
+/// assert_eq!(bitmap, [0b1111_1000, 0b0000_1111]);
+/// assert_eq!(
+///     set_range_checked(&mut bitmap, 12, 3),
+///     Err(RangeError { start: 12, end: 3, bits: 16 })
+/// );
+/// assert_eq!(
+///     set_range_checked(&mut bitmap, 3, 17),
+///     Err(RangeError { start: 3, end: 17, bits: 16 })
+/// );
+/// assert_eq!(bitmap, [0b1111_1000, 0b0000_1111]);
+/// ```
+#[inline]
+pub fn set_range_checked(bitmap: &mut [u8], start: usize, end: usize) -> Result<(), RangeError> {
+    check_range_bytes(bitmap.len(), start, end)?;
+    set_range(bitmap, start, end);
+    Ok(())
+}
+
+/// Like [`clear_range()`], but returns an error instead of panicking if the
+/// range is reversed or extends past the end of the bitmap
+///
+/// ```
+/// # use bit_test::{clear_range_checked, RangeError};
+/// let mut bitmap = [0xff; 2];
+/// assert_eq!(clear_range_checked(&mut bitmap, 3, 12), Ok(()));
+/// assert_eq!(bitmap, [0b0000_0111, 0b1111_0000]);
+/// assert!(clear_range_checked(&mut bitmap, 12, 3).is_err());
+/// assert!(clear_range_checked(&mut bitmap, 3, 17).is_err());
+/// assert_eq!(bitmap, [0b0000_0111, 0b1111_0000]);
+/// ```
+#[inline]
+pub fn clear_range_checked(bitmap: &mut [u8], start: usize, end: usize) -> Result<(), RangeError> {
+    check_range_bytes(bitmap.len(), start, end)?;
+    clear_range(bitmap, start, end);
+    Ok(())
+}
+
+/// Like [`test_range_all()`], but returns an error instead of panicking if the
+/// range is reversed or extends past the end of the bitmap
+///
+/// ```
+/// # use bit_test::{test_range_all_checked, RangeError};
+/// let bitmap = [0b1111_1000, 0b0000_1111];
+/// assert_eq!(test_range_all_checked(&bitmap, 3, 12), Ok(true));
+/// assert_eq!(test_range_all_checked(&bitmap, 2, 12), Ok(false));
+/// assert_eq!(
+///     test_range_all_checked(&bitmap, 12, 3),
+///     Err(RangeError { start: 12, end: 3, bits: 16 })
+/// );
+/// assert_eq!(
+///     test_range_all_checked(&bitmap, 16, 17),
+///     Err(RangeError { start: 16, end: 17, bits: 16 })
+/// );
+/// ```
+#[inline]
+pub fn test_range_all_checked(bitmap: &[u8], start: usize, end: usize) -> Result<bool, RangeError> {
+    check_range_bytes(bitmap.len(), start, end)?;
+    Ok(test_range_all(bitmap, start, end))
+}
+
+/// Check that `start <= end <= bits`, where `bits` is the number of bits of a
+/// bitmap of `len_bytes` bytes
+///
+/// As in [`check_bounds_bytes()`], every range end is within the bitmap when
+/// the number of bits doesn't fit in `usize`.
+#[inline]
+const fn check_range_bytes(len_bytes: usize, start: usize, end: usize) -> Result<(), RangeError> {
+    let bits = len_bytes.checked_mul(8);
+    let past_end = match bits {
+        Some(bits) => end > bits,
+        None => false,
+    };
+    if start > end || past_end {
+        Err(RangeError {
+            start,
+            end,
+            bits: len_bytes.saturating_mul(8),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+const _: () = {
+    assert!(check_range_bytes(2, 0, 16).is_ok());
+    assert!(check_range_bytes(2, 16, 16).is_ok());
+    assert!(check_range_bytes(2, 3, 2).is_err());
+    assert!(check_range_bytes(2, 0, 17).is_err());
+    assert!(check_range_bytes(usize::MAX / 4, 0, usize::MAX).is_ok());
+};
+
+/// Error returned when a bit range is reversed or extends past the end of a
+/// bitmap
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RangeError {
+    /// First bit of the range that was requested
+    pub start: usize,
+
+    /// End of the range that was requested, exclusive
+    pub end: usize,
+
+    /// Number of bits in the bitmap
+    pub bits: usize,
+}
+
+impl core::fmt::Display for RangeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.start > self.end {
+            write!(
+                f,
+                "bit range starts at {} but ends at {}",
+                self.start, self.end
+            )
+        } else {
+            write!(
+                f,
+                "bit range {}..{} is out of bounds for a bitmap of {} bits",
+                self.start, self.end, self.bits
+            )
+        }
+    }
+}
+
+impl core::error::Error for RangeError {}
+
+#[cfg(target_arch = "x86_64")]
+#[inline]
+pub fn set_all_avx2(bitmap: &mut [u8]) {
+    fill_avx2(bitmap, u8::MAX)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline]
+pub fn clear_all_avx2(bitmap: &mut [u8]) {
+    fill_avx2(bitmap, 0)
+}
+
+/// Fill a bitmap with a byte value using AVX2 stores if available, falling back
+/// to `slice::fill` otherwise
+///
+/// Without the `std` feature, AVX2 support can't be detected at runtime, so
+/// AVX2 stores are only used if AVX2 support is enabled at compile time.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+fn fill_avx2(bitmap: &mut [u8], value: u8) {
+    if has_avx2() {
+        // SAFETY: AVX2 support was checked above
+        unsafe { fill_avx2_unchecked(bitmap, value) }
+    } else {
+        bitmap.fill(value)
+    }
+}
+
+/// Truth that AVX2 instructions can be used, see [`fill_avx2()`]
+#[cfg(target_arch = "x86_64")]
+#[inline]
+fn has_avx2() -> bool {
+    #[cfg(feature = "std")]
+    {
+        is_x86_feature_detected!("avx2")
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        cfg!(target_feature = "avx2")
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+fn fill_avx2_unchecked(bitmap: &mut [u8], value: u8) {
+    use core::arch::x86_64::{__m256i, _mm256_set1_epi8, _mm256_storeu_si256};
+    let pattern = _mm256_set1_epi8(value as i8);
+    let (chunks, tail) = bitmap.as_chunks_mut::<32>();
+    for chunk in chunks {
+        // SAFETY: chunk is 32 bytes long and unaligned stores are used
+        unsafe { _mm256_storeu_si256(chunk.as_mut_ptr().cast::<__m256i>(), pattern) };
+    }
+    tail.fill(value);
+}
+
+/// Count the set bits of the half-open bit range `[start, end)` of a bitmap
+///
+/// Only the bytes at the edges of the range need masking, bytes which are
+/// fully covered by the range are counted like in [`count_ones()`].
+///
+/// ```
+/// # use bit_test::count_ones_in_range;
+/// let bitmap = [0b1111_0000, 0xff, 0b0000_0101];
+/// assert_eq!(count_ones_in_range(&bitmap, 6, 19), 12);
+/// assert_eq!(count_ones_in_range(&bitmap, 1, 6), 2);
+/// assert_eq!(count_ones_in_range(&bitmap, 9, 9), 0);
+/// ```
+#[inline]
+pub fn count_ones_in_range(bitmap: &[u8], start: usize, end: usize) -> usize {
+    let RangeBytes { head, middle, tail } = RangeBytes::new(start, end);
+    let partial_count = |(byte, mask): (usize, u8)| (bitmap[byte] & mask).count_ones() as usize;
+    head.map_or(0, partial_count) + count_ones(&bitmap[middle]) + tail.map_or(0, partial_count)
+}
+
+/// Count the set bits of each consecutive `bucket_bits`-bit bucket of a
+/// bitmap, e.g. to check how evenly the set bits are spread
+///
+/// A trailing partial bucket is counted like a full bucket. Buckets which
+/// are a whole number of bytes are counted one chunk of bytes at a time,
+/// whereas other bucket sizes require masking bytes that straddle two buckets.
+///
+/// ```
+/// # use bit_test::bucket_popcounts;
+/// let bitmap = [0xff, 0b0000_0001, 0b1000_0000, 0, 0b0001_0000];
+/// assert_eq!(bucket_popcounts(&bitmap, 16), [9, 1, 1]);
+/// assert_eq!(bucket_popcounts(&bitmap, 12), [9, 1, 0, 1]);
+/// ```
+#[cfg(feature = "std")]
+pub fn bucket_popcounts(bitmap: &[u8], bucket_bits: usize) -> Vec<u32> {
+    assert!(bucket_bits > 0, "buckets should contain at least one bit");
+    let to_u32 = |count: usize| u32::try_from(count).expect("bit count does not fit in u32");
+    if bucket_bits.is_multiple_of(8) {
+        bitmap
+            .chunks(bucket_bits / 8)
+            .map(|bucket| to_u32(count_ones(bucket)))
+            .collect()
+    } else {
+        let bits = bitmap.len() * 8;
+        (0..bits)
+            .step_by(bucket_bits)
+            .map(|start| {
+                let end = (start + bucket_bits).min(bits);
+                to_u32(count_ones_in_range(bitmap, start, end))
+            })
+            .collect()
+    }
+}
+
+/// Decomposition of a half-open bit range `[start, end)` into partially
+/// covered bytes at the edges, which are described by a byte index and the
+/// mask of covered bits, and fully covered bytes in the middle
+struct RangeBytes {
+    head: Option<(usize, u8)>,
+    middle: core::ops::Range<usize>,
+    tail: Option<(usize, u8)>,
+}
+
+impl RangeBytes {
+    #[inline]
+    fn new(start: usize, end: usize) -> Self {
+        assert!(
+            start <= end,
+            "bit range starts at {start} but ends at {end}"
+        );
+        let (start_byte, start_bit) = (start >> 3, start & 7);
+        let (end_byte, end_bit) = (end >> 3, end & 7);
+        let low_mask = |bit: usize| (1u8 << bit) - 1;
+        if start_byte == end_byte {
+            let mask = low_mask(end_bit) & !low_mask(start_bit);
+            return Self {
+                head: (mask != 0).then_some((start_byte, mask)),
+                middle: 0..0,
+                tail: None,
+            };
+        }
+        let (head, middle_start) = if start_bit == 0 {
+            (None, start_byte)
+        } else {
+            (Some((start_byte, !low_mask(start_bit))), start_byte + 1)
+        };
+        let tail = (end_bit != 0).then_some((end_byte, low_mask(end_bit)));
+        Self {
+            head,
+            middle: middle_start..end_byte,
+            tail,
+        }
+    }
+}
+
+/// Compute `dst &= src` for each byte
+///
+/// Behavior is only defined for inputs of equal length, which is checked in
+/// debug builds.
+#[inline]
+pub fn bitmap_and(dst: &mut [u8], src: &[u8]) {
+    debug_assert_eq!(dst.len(), src.len(), "bitmaps should have equal length");
+    for (dst, src) in dst.iter_mut().zip(src) {
+        *dst &= src;
+    }
+}
+
+/// Compute `dst |= src` for each byte
+///
+/// Behavior is only defined for inputs of equal length, which is checked in
+/// debug builds.
+#[inline]
+pub fn bitmap_or(dst: &mut [u8], src: &[u8]) {
+    debug_assert_eq!(dst.len(), src.len(), "bitmaps should have equal length");
+    for (dst, src) in dst.iter_mut().zip(src) {
+        *dst |= src;
+    }
+}
+
+/// Compute `dst ^= src` for each byte
+///
+/// Behavior is only defined for inputs of equal length, which is checked in
+/// debug builds.
+#[inline]
+pub fn bitmap_xor(dst: &mut [u8], src: &[u8]) {
+    debug_assert_eq!(dst.len(), src.len(), "bitmaps should have equal length");
+    for (dst, src) in dst.iter_mut().zip(src) {
+        *dst ^= src;
+    }
+}
+
+/// Compute `dst &= !src` for each byte, i.e. the set difference
+///
+/// Behavior is only defined for inputs of equal length, which is checked in
+/// debug builds.
+#[inline]
+pub fn bitmap_andnot(dst: &mut [u8], src: &[u8]) {
+    debug_assert_eq!(dst.len(), src.len(), "bitmaps should have equal length");
+    for (dst, src) in dst.iter_mut().zip(src) {
+        *dst &= !src;
+    }
+}
+
+/// Generate an AVX2 version of a set algebra operation, which falls back to
+/// the scalar version if AVX2 is not available
+macro_rules! impl_setalg_avx2 {
+    ($(#[$attr:meta])* $name:ident => $scalar:ident, $intrinsic:ident) => {
+        $(#[$attr])*
+        #[cfg(target_arch = "x86_64")]
+        #[inline]
+        pub fn $name(dst: &mut [u8], src: &[u8]) {
+            debug_assert_eq!(dst.len(), src.len(), "bitmaps should have equal length");
+            #[target_feature(enable = "avx2")]
+            fn unchecked(dst: &mut [u8], src: &[u8]) {
+                use core::arch::x86_64::{
+                    __m256i, _mm256_loadu_si256, _mm256_storeu_si256, $intrinsic,
+                };
+                let (dst_chunks, dst_tail) = dst.as_chunks_mut::<32>();
+                let (src_chunks, src_tail) = src.as_chunks::<32>();
+                for (dst, src) in dst_chunks.iter_mut().zip(src_chunks) {
+                    // SAFETY: chunks are 32 bytes long and unaligned loads and
+                    //         stores are used
+                    unsafe {
+                        let lhs = _mm256_loadu_si256(dst.as_ptr().cast::<__m256i>());
+                        let rhs = _mm256_loadu_si256(src.as_ptr().cast::<__m256i>());
+                        let result = $intrinsic(lhs, rhs);
+                        _mm256_storeu_si256(dst.as_mut_ptr().cast::<__m256i>(), result);
+                    }
+                }
+                $scalar(dst_tail, src_tail)
+            }
+            if has_avx2() {
+                // SAFETY: AVX2 support was checked above
+                unsafe { unchecked(dst, src) }
+            } else {
+                $scalar(dst, src)
+            }
+        }
+    };
+}
+impl_setalg_avx2!(
+    /// Like [`bitmap_and()`], but uses AVX2 intrinsics if available
+    bitmap_and_avx2 => bitmap_and, _mm256_and_si256
+);
+impl_setalg_avx2!(
+    /// Like [`bitmap_or()`], but uses AVX2 intrinsics if available
+    bitmap_or_avx2 => bitmap_or, _mm256_or_si256
+);
+impl_setalg_avx2!(
+    /// Like [`bitmap_xor()`], but uses AVX2 intrinsics if available
+    bitmap_xor_avx2 => bitmap_xor, _mm256_xor_si256
+);
+
+/// Number of bytes processed at once by the portable SIMD set algebra
+/// operations, which matches the width of the widest vector registers that
+/// the target is known to support at compile time
+#[cfg(feature = "portable_simd")]
+const SETALG_SIMD_LANES: usize = if cfg!(target_feature = "avx512f") {
+    64
+} else if cfg!(target_feature = "avx2") {
+    32
+} else {
+    16
+};
+
+/// Generate a portable SIMD version of a set algebra operation, which handles
+/// the bytes that don't fill a whole vector using the scalar version
+///
+/// Unlike the AVX2 versions, these work on any target that `std::simd`
+/// supports, and are thus the recommended cross-platform implementation.
+macro_rules! impl_setalg_simd {
+    ($(#[$attr:meta])* $name:ident => $scalar:ident, $op:tt) => {
+        $(#[$attr])*
+        #[cfg(feature = "portable_simd")]
+        #[inline]
+        pub fn $name(dst: &mut [u8], src: &[u8]) {
+            use core::simd::Simd;
+            debug_assert_eq!(dst.len(), src.len(), "bitmaps should have equal length");
+            let (dst_chunks, dst_tail) = dst.as_chunks_mut::<SETALG_SIMD_LANES>();
+            let (src_chunks, src_tail) = src.as_chunks::<SETALG_SIMD_LANES>();
+            for (dst, src) in dst_chunks.iter_mut().zip(src_chunks) {
+                *dst = (Simd::from_array(*dst) $op Simd::from_array(*src)).to_array();
+            }
+            $scalar(dst_tail, src_tail)
+        }
+    };
+}
+impl_setalg_simd!(
+    /// Like [`bitmap_and()`], but uses portable SIMD
+    bitmap_and_simd => bitmap_and, &
+);
+impl_setalg_simd!(
+    /// Like [`bitmap_or()`], but uses portable SIMD
+    bitmap_or_simd => bitmap_or, |
+);
+impl_setalg_simd!(
+    /// Like [`bitmap_xor()`], but uses portable SIMD
+    bitmap_xor_simd => bitmap_xor, ^
+);
+
+/// Number of bits that are set in both `a` and `b`
+///
+/// Behavior is only defined for inputs of equal length, which is checked in
+/// debug builds.
+#[inline]
+pub fn and_count(a: &[u8], b: &[u8]) -> usize {
+    debug_assert_eq!(a.len(), b.len(), "bitmaps should have equal length");
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a & b).count_ones() as usize)
+        .sum()
+}
+
+/// Number of bits that are set in `a`, `b`, or both
+///
+/// Behavior is only defined for inputs of equal length, which is checked in
+/// debug builds.
+#[inline]
+pub fn or_count(a: &[u8], b: &[u8]) -> usize {
+    debug_assert_eq!(a.len(), b.len(), "bitmaps should have equal length");
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a | b).count_ones() as usize)
+        .sum()
+}
+
+/// Number of bits that are set in either `a` or `b`, but not both
+///
+/// Behavior is only defined for inputs of equal length, which is checked in
+/// debug builds.
+#[inline]
+pub fn xor_count(a: &[u8], b: &[u8]) -> usize {
+    debug_assert_eq!(a.len(), b.len(), "bitmaps should have equal length");
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a ^ b).count_ones() as usize)
+        .sum()
+}
+
+/// Version of `bit_test_naive` that can be used in const contexts
+#[inline]
+pub const fn bit_test_const_fn(bitmap: &[u8], idx: usize) -> bool {
+    bitmap[idx / 8] & (1 << (idx % 8)) != 0
+}
+
+// Check at compile time that bit_test_const_fn can be evaluated in const contexts
+const _: () = {
+    const BITMAP: [u8; 2] = [0b0000_0100, 0b1000_0000];
+    assert!(bit_test_const_fn(&BITMAP, 2));
+    assert!(!bit_test_const_fn(&BITMAP, 3));
+    assert!(bit_test_const_fn(&BITMAP, 15));
+};
+
+/// Test a bit using MSB-first bit numbering, where bit 0 is the most
+/// significant bit of byte 0, as is common in network protocols
+///
+/// All other functions of this crate use LSB-first bit numbering.
+///
+/// ```
+/// # use bit_test::{bit_set_msb, bit_set_naive};
+/// let (mut msb_first, mut lsb_first) = ([0u8], [0u8]);
+/// bit_set_msb(&mut msb_first, 0);
+/// bit_set_naive(&mut lsb_first, 0);
+/// assert_eq!(msb_first, [0x80]);
+/// assert_eq!(lsb_first, [0x01]);
+/// ```
+#[inline]
+pub fn bit_test_msb(bitmap: &[u8], idx: usize) -> bool {
+    bitmap[idx >> 3] & (0x80 >> (idx & 7)) != 0
+}
+
+#[inline]
+pub fn bit_set_msb(bitmap: &mut [u8], idx: usize) {
+    bitmap[idx >> 3] |= 0x80 >> (idx & 7)
+}
+
+#[inline]
+pub fn bit_clear_msb(bitmap: &mut [u8], idx: usize) {
+    bitmap[idx >> 3] &= !(0x80 >> (idx & 7))
+}
+
+#[inline]
+pub fn bit_test_msb_table(bitmap: &[u8], idx: usize) -> bool {
+    bitmap[idx >> 3] & MSB_BIT_MASK[idx & 7] != 0
+}
+
+#[inline]
+pub fn bit_set_msb_table(bitmap: &mut [u8], idx: usize) {
+    bitmap[idx >> 3] |= MSB_BIT_MASK[idx & 7]
+}
+
+#[inline]
+pub fn bit_clear_msb_table(bitmap: &mut [u8], idx: usize) {
+    bitmap[idx >> 3] &= !MSB_BIT_MASK[idx & 7]
+}
+
+const MSB_BIT_MASK: [u8; 8] = [128, 64, 32, 16, 8, 4, 2, 1];
+
+/// Index of the byte which contains bit `idx`, and mask of that bit within the
+/// byte, for use in custom read-modify-write sequences
+///
+/// ```
+/// # use bit_test::{bit_location, bit_location_msb};
+/// assert_eq!(bit_location(10), (1, 0b100));
+/// assert_eq!(bit_location_msb(10), (1, 0b0010_0000));
+///
+/// let mut bitmap = [0u8; 2];
+/// let (byte, mask) = bit_location(10);
+/// bitmap[byte] |= mask;
+/// assert_eq!(bitmap, [0, 0b100]);
+/// ```
+#[inline]
+pub const fn bit_location(idx: usize) -> (usize, u8) {
+    (idx >> 3, BIT_MASK[idx & 7])
+}
+
+/// Like [`bit_location()`], but for MSB-first bit numbering
+#[inline]
+pub const fn bit_location_msb(idx: usize) -> (usize, u8) {
+    (idx >> 3, MSB_BIT_MASK[idx & 7])
+}
+
+/// Extract the `count` bits starting at bit `start`, right-aligned in a `u64`
+///
+/// `count` must not be greater than 64. Bit `start` of the bitmap becomes the
+/// least significant bit of the result.
+///
+/// ```
+/// # use bit_test::{get_bits, put_bits};
+/// let mut bitmap = [0u8; 4];
+/// put_bits(&mut bitmap, 5, 13, 0b1_0110_1001_1101);
+/// assert_eq!(bitmap, [0b1010_0000, 0b1101_0011, 0b0000_0010, 0]);
+/// assert_eq!(get_bits(&bitmap, 5, 13), 0b1_0110_1001_1101);
+/// ```
+#[inline]
+pub fn get_bits(bitmap: &[u8], start: usize, count: usize) -> u64 {
+    let (bytes, shift) = field_bytes(start, count);
+    let mut acc = 0u128;
+    for (i, &byte) in bitmap[bytes].iter().enumerate() {
+        acc |= u128::from(byte) << (8 * i);
+    }
+    (acc >> shift) as u64 & low_mask_u64(count)
+}
+
+/// Write the `count` low-order bits of `value` into the bitmap, starting at
+/// bit `start`, leaving the other bits of the bitmap unchanged
+///
+/// `count` must not be greater than 64. Higher-order bits of `value` are
+/// ignored.
+#[inline]
+pub fn put_bits(bitmap: &mut [u8], start: usize, count: usize, value: u64) {
+    let (bytes, shift) = field_bytes(start, count);
+    let mask = u128::from(low_mask_u64(count)) << shift;
+    let value = (u128::from(value) << shift) & mask;
+    for (i, byte) in bitmap[bytes].iter_mut().enumerate() {
+        let byte_mask = (mask >> (8 * i)) as u8;
+        *byte = (*byte & !byte_mask) | (value >> (8 * i)) as u8;
+    }
+}
+
+/// Extract the 16 bits starting at bit `bit_offset`, like
+/// [`get_bits(bitmap, bit_offset, 16)`](get_bits) but faster
+///
+/// The bits are extracted from a two-byte window, and from the next byte if
+/// `bit_offset` is not a multiple of 8, since the bits then span three bytes.
+///
+/// ```
+/// # use bit_test::{get_bits, read_u16_at};
+/// let bitmap = [0b1010_0000, 0b1101_0011, 0b1111_0010];
+/// assert_eq!(read_u16_at(&bitmap, 0), 0b1101_0011_1010_0000);
+/// assert_eq!(read_u16_at(&bitmap, 5), 0b1001_0110_1001_1101);
+/// assert_eq!(read_u16_at(&bitmap, 5) as u64, get_bits(&bitmap, 5, 16));
+/// assert_eq!(read_u16_at(&bitmap, 5) as u64 & 0x1ff, get_bits(&bitmap, 5, 9));
+/// ```
+#[inline]
+pub fn read_u16_at(bitmap: &[u8], bit_offset: usize) -> u16 {
+    let first_byte = bit_offset >> 3;
+    let shift = bit_offset & 7;
+    let window = u16::from_le_bytes([bitmap[first_byte], bitmap[first_byte + 1]]);
+    if shift == 0 {
+        return window;
+    }
+    (window >> shift) | (u16::from(bitmap[first_byte + 2]) << (16 - shift))
+}
+
+/// Range of bytes covered by a bit field, and position of the field's first
+/// bit within the first byte
+#[inline]
+fn field_bytes(start: usize, count: usize) -> (core::ops::Range<usize>, usize) {
+    assert!(count <= 64, "can't access more than 64 bits at once");
+    let first_byte = start >> 3;
+    let shift = start & 7;
+    (first_byte..first_byte + (shift + count).div_ceil(8), shift)
+}
+
+/// Mask of the `count` low-order bits of a `u64`
+#[inline]
+fn low_mask_u64(count: usize) -> u64 {
+    if count >= 64 {
+        u64::MAX
+    } else {
+        (1 << count) - 1
+    }
+}
+
+/// Set all bits of the bitmap to `value`
+///
+/// This is much faster than changing each bit individually.
+#[inline]
+pub fn fill(bitmap: &mut [u8], value: bool) {
+    bitmap.fill(if value { u8::MAX } else { 0 })
+}
+
+/// Clear all bits of the bitmap
+#[inline]
+pub fn reset(bitmap: &mut [u8]) {
+    fill(bitmap, false)
+}
+
+/// Add 1 to the little-endian integer that the bitmap represents, carrying
+/// across bytes, and return `true` if it wrapped around to zero
+///
+/// Starting from an all-clear bitmap, this sweeps through all bit patterns.
+/// An empty bitmap has a single bit pattern, so it always wraps around.
+///
+/// ```
+/// # use bit_test::increment;
+/// let mut bitmap = [0xff];
+/// assert!(increment(&mut bitmap));
+/// assert_eq!(bitmap, [0x00]);
+///
+/// let mut bitmap = [0xff, 0x00];
+/// assert!(!increment(&mut bitmap));
+/// assert_eq!(bitmap, [0x00, 0x01]);
+///
+/// assert!(increment(&mut []));
+/// ```
+#[inline]
+pub fn increment(bitmap: &mut [u8]) -> bool {
+    for byte in bitmap {
+        let (sum, carry) = byte.overflowing_add(1);
+        *byte = sum;
+        if !carry {
+            return false;
+        }
+    }
+    true
+}
+
+/// Turn the bitmap into the next code of the reflected binary Gray code, by
+/// flipping a single bit, and return `true` if it wrapped around to zero
+///
+/// Like [`increment()`], starting from an all-clear bitmap, this sweeps
+/// through all bit patterns, but consecutive patterns only differ by one bit.
+/// An empty bitmap has a single bit pattern, so it always wraps around.
+///
+/// ```
+/// # use bit_test::gray_code_successor;
+/// let mut bitmap = [0u8];
+/// let mut codes = Vec::new();
+/// for _ in 0..8 {
+///     codes.push(bitmap[0]);
+///     gray_code_successor(&mut bitmap);
+/// }
+/// assert_eq!(codes, [0b000, 0b001, 0b011, 0b010, 0b110, 0b111, 0b101, 0b100]);
+///
+/// let mut bitmap = [0, 0b1000_0000];
+/// assert!(gray_code_successor(&mut bitmap));
+/// assert_eq!(bitmap, [0, 0]);
+///
+/// assert!(gray_code_successor(&mut []));
+/// ```
+#[inline]
+pub fn gray_code_successor(bitmap: &mut [u8]) -> bool {
+    if count_ones(bitmap).is_multiple_of(2) {
+        let Some(first) = bitmap.first_mut() else {
+            return true;
+        };
+        *first ^= 1;
+        return false;
+    }
+    let lowest_set = find_first_set(bitmap).expect("odd parity implies a set bit");
+    let num_bits = bitmap.len() * 8;
+    if lowest_set + 1 < num_bits {
+        bit_toggle_const_table(bitmap, lowest_set + 1);
+        false
+    } else {
+        bit_toggle_const_table(bitmap, lowest_set);
+        true
+    }
+}
+
+/// Flip all bits of the bitmap, turning it into the complement of the set it
+/// represents
+///
+/// Padding bits of the last byte, if any, are flipped too. See
+/// [`Bitmap::complement()`] for a version which keeps them clear.
+#[inline]
+pub fn complement(bitmap: &mut [u8]) {
+    for byte in bitmap {
+        *byte = !*byte;
+    }
+}
+
+/// Fixed-size bitmap of `BITS` bits stored inline in `BYTES` bytes
+///
+/// Computing `BYTES` from `BITS` in the type would require the unstable
+/// `generic_const_exprs` feature, so it must be specified separately. It is
+/// checked at compile time to be equal to `BITS.div_ceil(8)`.
+///
+/// ```
+/// # use bit_test::BitArray;
+/// let mut bits = BitArray::<64, 8>::new();
+/// bits.set(3);
+/// bits.set(42);
+/// bits.clear(3);
+/// assert!(!bits.test(3));
+/// assert!(bits.test(42));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BitArray<const BITS: usize, const BYTES: usize> {
+    bytes: [u8; BYTES],
+}
+
+impl<const BITS: usize, const BYTES: usize> BitArray<BITS, BYTES> {
+    /// Create a bit array where all bits are initially clear
+    pub const fn new() -> Self {
+        const {
+            assert!(
+                BYTES == BITS.div_ceil(8),
+                "BYTES should be the number of bytes needed to store BITS bits"
+            )
+        };
+        Self { bytes: [0; BYTES] }
+    }
+
+    /// Number of bits in the array
+    #[inline]
+    pub const fn len(&self) -> usize {
+        BITS
+    }
+
+    /// Truth that the array contains no bits
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        BITS == 0
+    }
+
+    #[inline]
+    pub fn test(&self, idx: usize) -> bool {
+        Self::check_index(idx);
+        bit_test_const_table(&self.bytes, idx)
+    }
+
+    #[inline]
+    pub fn set(&mut self, idx: usize) {
+        Self::check_index(idx);
+        bit_set_const_table(&mut self.bytes, idx)
+    }
+
+    #[inline]
+    pub fn clear(&mut self, idx: usize) {
+        Self::check_index(idx);
+        bit_clear_const_table(&mut self.bytes, idx)
+    }
+
+    /// Panic if `idx` is not a valid bit index, even if the byte that would
+    /// contain it exists in the backing storage
+    #[inline]
+    fn check_index(idx: usize) {
+        assert!(
+            idx < BITS,
+            "bit index {idx} is out of bounds for a bit array of {BITS} bits"
+        );
+    }
+}
+
+impl<const BITS: usize, const BYTES: usize> Default for BitArray<BITS, BYTES> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reverse the order of bits within each byte, which converts between
+/// LSB-first and MSB-first bit numbering
+///
+/// ```
+/// # use bit_test::reverse_byte_bits;
+/// let mut bitmap = [0b0000_0001, 0b0000_0110];
+/// reverse_byte_bits(&mut bitmap);
+/// assert_eq!(bitmap, [0b1000_0000, 0b0110_0000]);
+/// ```
+#[inline]
+pub fn reverse_byte_bits(bitmap: &mut [u8]) {
+    for byte in bitmap {
+        *byte = byte.reverse_bits();
+    }
+}
+
+/// Reverse the order of all bits in the bitmap, so that bit 0 is swapped with
+/// the last bit, bit 1 with the penultimate bit, and so on
+#[inline]
+pub fn reverse_all_bits(bitmap: &mut [u8]) {
+    bitmap.reverse();
+    reverse_byte_bits(bitmap);
+}
+
+/// Truth that two bitmaps have the same bits set
+///
+/// Behavior is only defined for inputs of equal length, which is checked in
+/// debug builds.
+#[inline]
+pub fn bitmaps_equal(a: &[u8], b: &[u8]) -> bool {
+    debug_assert_eq!(a.len(), b.len(), "bitmaps should have equal length");
+    a == b
+}
+
+/// Truth that every bit which is set in `a` is also set in `b`
+///
+/// Behavior is only defined for inputs of equal length, which is checked in
+/// debug builds.
+#[inline]
+pub fn is_subset(a: &[u8], b: &[u8]) -> bool {
+    debug_assert_eq!(a.len(), b.len(), "bitmaps should have equal length");
+    a.iter().zip(b).all(|(a, b)| a & !b == 0)
+}
+
+/// Truth that no bit is set in both `a` and `b`
+///
+/// Behavior is only defined for inputs of equal length, which is checked in
+/// debug builds.
+#[inline]
+pub fn is_disjoint(a: &[u8], b: &[u8]) -> bool {
+    debug_assert_eq!(a.len(), b.len(), "bitmaps should have equal length");
+    a.iter().zip(b).all(|(a, b)| a & b == 0)
+}
+
+/// Encode a bitmap as a lowercase hexadecimal string, two digits per byte
+#[cfg(feature = "std")]
+pub fn to_hex(bitmap: &[u8]) -> String {
+    use std::fmt::Write;
+    let mut hex = String::with_capacity(bitmap.len() * 2);
+    for byte in bitmap {
+        write!(hex, "{byte:02x}").unwrap();
+    }
+    hex
+}
+
+/// Decode a bitmap from a hexadecimal string, as produced by [`to_hex()`]
+///
+/// Both lowercase and uppercase digits are accepted.
+///
+/// ```
+/// # use bit_test::{from_hex, to_hex};
+/// let bitmap = [0x00, 0x2a, 0xff];
+/// assert_eq!(to_hex(&bitmap), "002aff");
+/// assert_eq!(from_hex("002aff"), Ok(bitmap.to_vec()));
+/// assert!(from_hex("02a").is_err());
+/// assert!(from_hex("0g").is_err());
+/// ```
+#[cfg(feature = "std")]
+pub fn from_hex(s: &str) -> Result<Vec<u8>, ParseError> {
+    if let Some((position, character)) = s.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        return Err(ParseError::InvalidCharacter {
+            position,
+            character,
+        });
+    }
+    if !s.len().is_multiple_of(2) {
+        return Err(ParseError::OddLength { len: s.len() });
+    }
+    let digit = |character: u8| (character as char).to_digit(16).unwrap() as u8;
+    Ok(s.as_bytes()
+        .chunks_exact(2)
+        .map(|digits| (digit(digits[0]) << 4) | digit(digits[1]))
+        .collect())
+}
+
+/// Error returned when parsing a bitmap from a string fails
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseError {
+    /// Hexadecimal strings must contain two digits per byte
+    OddLength { len: usize },
+
+    /// Encountered a character that is not a valid digit, at some byte offset
+    /// within the input string
+    InvalidCharacter { position: usize, character: char },
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::OddLength { len } => {
+                write!(f, "expected an even number of hex digits, got {len}")
+            }
+            Self::InvalidCharacter {
+                position,
+                character,
+            } => write!(f, "invalid digit {character:?} at position {position}"),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+/// Render a bitmap as a string of '0' and '1' characters, optionally with a
+/// separator between bytes
+///
+/// Bits are rendered in index order, i.e. bit 0 comes first. Beware that this
+/// is the opposite of the usual `{:08b}` rendering of a byte, which puts its
+/// most significant bit first.
+///
+/// ```
+/// # use bit_test::{bit_set_naive, to_binary_string};
+/// let mut bitmap = [0u8; 2];
+/// bit_set_naive(&mut bitmap, 0);
+/// bit_set_naive(&mut bitmap, 10);
+/// assert_eq!(to_binary_string(&bitmap, None), "1000000000100000");
+/// assert_eq!(to_binary_string(&bitmap, Some('_')), "10000000_00100000");
+/// ```
+#[cfg(feature = "std")]
+pub fn to_binary_string(bitmap: &[u8], separator: Option<char>) -> String {
+    let mut binary = String::with_capacity(bitmap.len() * 9);
+    for (byte_idx, byte) in bitmap.iter().enumerate() {
+        if let Some(separator) = separator.filter(|_| byte_idx != 0) {
+            binary.push(separator);
+        }
+        for bit in 0..8 {
+            binary.push(if byte & (1 << bit) != 0 { '1' } else { '0' });
+        }
+    }
+    binary
+}
+
+/// Parse a bitmap from a string of '0' and '1' characters in index order, as
+/// produced by [`to_binary_string()`]
+///
+/// Occurrences of `separator` are ignored. If the number of bits is not a
+/// multiple of 8, the last byte is padded with clear bits.
+///
+/// ```
+/// # use bit_test::{from_binary_string, to_binary_string, ParseError};
+/// let bitmap = [0b0010_0001, 0b1000_0100];
+/// let binary = to_binary_string(&bitmap, Some('_'));
+/// assert_eq!(binary, "10000100_00100001");
+/// assert_eq!(from_binary_string(&binary, Some('_')), Ok(bitmap.to_vec()));
+///
+/// assert_eq!(from_binary_string("1000000001", None), Ok(vec![0b1, 0b10]));
+/// assert_eq!(
+///     from_binary_string("10_2", Some('_')),
+///     Err(ParseError::InvalidCharacter { position: 3, character: '2' })
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn from_binary_string(s: &str, separator: Option<char>) -> Result<Vec<u8>, ParseError> {
+    let mut bitmap = Vec::with_capacity(s.len() / 8);
+    let mut num_bits = 0usize;
+    for (position, character) in s.char_indices() {
+        let bit = match character {
+            '0' => false,
+            '1' => true,
+            _ if Some(character) == separator => continue,
+            _ => {
+                return Err(ParseError::InvalidCharacter {
+                    position,
+                    character,
+                })
+            }
+        };
+        if num_bits.is_multiple_of(8) {
+            bitmap.push(0);
+        }
+        if bit {
+            bit_set_naive(&mut bitmap, num_bits);
+        }
+        num_bits += 1;
+    }
+    Ok(bitmap)
+}
+
+/// Get a bit as an integer which is 0 if the bit is clear and 1 if it is set
+///
+/// This is naturally branch-free, and therefore preferable to `bit_test(..) as
+/// u8` when accumulating bit values in arithmetic computations.
+#[inline]
+pub fn bit_get_u8(bitmap: &[u8], idx: usize) -> u8 {
+    (bitmap[idx >> 3] >> (idx & 7)) & 1
+}
+
+/// Test a bit and also return the byte that contains it
+///
+/// This is a building block for batches of operations on bits of the same
+/// byte: the caller can test the other bits of the returned byte, or update
+/// it and store it back, without loading it from the bitmap again.
+///
+/// ```
+/// # use bit_test::bit_test_byte;
+/// let bitmap = [0, 0b0010_0110];
+/// let (bit, byte) = bit_test_byte(&bitmap, 9);
+/// assert!(bit);
+/// assert_eq!(byte, 0b0010_0110);
+/// assert_eq!(byte.count_ones(), 3);
+/// ```
+#[inline]
+pub fn bit_test_byte(bitmap: &[u8], idx: usize) -> (bool, u8) {
+    let byte = bitmap[idx >> 3];
+    (byte & BIT_MASK[idx & 7] != 0, byte)
+}
+
+/// Unsigned integer type which can be used as the storage word of a bitmap
+pub trait BitStorage: Copy {
+    /// Number of bits in a word
+    const BITS: usize;
+
+    /// Truth that bit `bit` of the word is set, where `bit < Self::BITS`
+    fn get_bit(self, bit: usize) -> bool;
+
+    /// Set bit `bit` of the word, where `bit < Self::BITS`
+    fn set_bit(&mut self, bit: usize);
+
+    /// Clear bit `bit` of the word, where `bit < Self::BITS`
+    fn clear_bit(&mut self, bit: usize);
+}
+
+macro_rules! impl_bit_storage {
+    ($($word:ty),*) => {
+        $(
+            impl BitStorage for $word {
+                const BITS: usize = <$word>::BITS as usize;
+
+                #[inline]
+                fn get_bit(self, bit: usize) -> bool {
+                    self & (1 << bit) != 0
+                }
+
+                #[inline]
+                fn set_bit(&mut self, bit: usize) {
+                    *self |= 1 << bit
+                }
+
+                #[inline]
+                fn clear_bit(&mut self, bit: usize) {
+                    *self &= !(1 << bit)
+                }
+            }
+        )*
+    };
+}
+impl_bit_storage!(u8, u16, u32, u64, usize);
+
+/// Test a bit of a bitmap made of words of any supported width
+///
+/// ```
+/// # use bit_test::{bit_set_generic, bit_test_generic};
+/// let mut bytes = [0u8; 2];
+/// let mut words = [0u64; 1];
+/// bit_set_generic(&mut bytes, 9);
+/// bit_set_generic(&mut words, 9);
+/// assert_eq!(bytes, [0, 0b10]);
+/// assert_eq!(words, [0b10_0000_0000]);
+/// assert!(bit_test_generic(&bytes, 9) && bit_test_generic(&words, 9));
+/// ```
+#[inline]
+pub fn bit_test_generic<W: BitStorage>(words: &[W], idx: usize) -> bool {
+    words[idx / W::BITS].get_bit(idx % W::BITS)
+}
+
+#[inline]
+pub fn bit_set_generic<W: BitStorage>(words: &mut [W], idx: usize) {
+    words[idx / W::BITS].set_bit(idx % W::BITS)
+}
+
+#[inline]
+pub fn bit_clear_generic<W: BitStorage>(words: &mut [W], idx: usize) {
+    words[idx / W::BITS].clear_bit(idx % W::BITS)
+}
+
+/// Test a bit of a bitmap that is designated by a raw pointer
+///
+/// # Safety
+///
+/// `bitmap` must be valid for reads of at least `idx / 8 + 1` bytes.
+#[inline]
+pub unsafe fn bit_test_ptr(bitmap: *const u8, idx: usize) -> bool {
+    unsafe { *bitmap.add(idx >> 3) & (1 << (idx & 7)) != 0 }
+}
+
+/// Set a bit of a bitmap that is designated by a raw pointer
+///
+/// # Safety
+///
+/// `bitmap` must be valid for reads and writes of at least `idx / 8 + 1` bytes.
+#[inline]
+pub unsafe fn bit_set_ptr(bitmap: *mut u8, idx: usize) {
+    unsafe { *bitmap.add(idx >> 3) |= 1 << (idx & 7) }
+}
+
+/// Clear a bit of a bitmap that is designated by a raw pointer
+///
+/// # Safety
+///
+/// `bitmap` must be valid for reads and writes of at least `idx / 8 + 1` bytes.
+#[inline]
+pub unsafe fn bit_clear_ptr(bitmap: *mut u8, idx: usize) {
+    unsafe { *bitmap.add(idx >> 3) &= !(1 << (idx & 7)) }
+}
+
+/// Test a bit of one of several bitmaps, which is selected at runtime
+///
+/// ```
+/// # use bit_test::bit_test_select;
+/// let (read, write) = ([0b0000_0110], [0b0000_0010]);
+/// let permissions = [&read[..], &write[..]];
+/// assert!(bit_test_select(&permissions, 0, 2));
+/// assert!(!bit_test_select(&permissions, 1, 2));
+/// ```
+#[inline]
+pub fn bit_test_select(bitmaps: &[&[u8]], which: usize, idx: usize) -> bool {
+    bit_test_const_table(bitmaps[which], idx)
+}
+
+/// Like [`bit_test_select()`], but without bounds checks
+///
+/// # Safety
+///
+/// `which` must be smaller than `bitmaps.len()`, and `bitmaps[which]` must be
+/// at least `idx / 8 + 1` bytes long.
+#[inline]
+pub unsafe fn bit_test_select_unchecked(bitmaps: &[&[u8]], which: usize, idx: usize) -> bool {
+    unsafe {
+        let bitmap = *bitmaps.get_unchecked(which);
+        *bitmap.get_unchecked(idx >> 3) & BIT_MASK[idx & 7] != 0
+    }
+}
+
+/// Copy `count` bits from `src`, starting at bit `src_start`, into `dst`,
+/// starting at bit `dst_start`
+///
+/// Whole bytes are copied directly when both ranges start on a byte boundary.
+/// Otherwise, bits are shifted into place up to 64 at a time.
+#[inline]
+pub fn copy_bits(dst: &mut [u8], dst_start: usize, src: &[u8], src_start: usize, count: usize) {
+    let mut copied = 0;
+    if dst_start.is_multiple_of(8) && src_start.is_multiple_of(8) {
+        let num_bytes = count / 8;
+        dst[dst_start / 8..][..num_bytes].copy_from_slice(&src[src_start / 8..][..num_bytes]);
+        copied = num_bytes * 8;
+    }
+    while copied < count {
+        let chunk = (count - copied).min(64);
+        let bits = get_bits(src, src_start + copied, chunk);
+        put_bits(dst, dst_start + copied, chunk, bits);
+        copied += chunk;
+    }
+}
+
+/// Rotate all bits of the bitmap by `n` positions towards higher indices,
+/// moving the bits that fall off the end back to the beginning
+#[inline]
+pub fn rotate_left(bitmap: &mut [u8], n: usize) {
+    let num_bits = bitmap.len() * 8;
+    if num_bits == 0 {
+        return;
+    }
+    let n = n % num_bits;
+    bitmap.rotate_right(n / 8);
+    let shift = n % 8;
+    if shift != 0 {
+        let mut carry = bitmap[bitmap.len() - 1] >> (8 - shift);
+        for byte in bitmap {
+            let next_carry = *byte >> (8 - shift);
+            *byte = (*byte << shift) | carry;
+            carry = next_carry;
+        }
+    }
+}
+
+/// Rotate all bits of the bitmap by `n` positions towards lower indices,
+/// moving the bits that fall off the beginning back to the end
+#[inline]
+pub fn rotate_right(bitmap: &mut [u8], n: usize) {
+    let num_bits = bitmap.len() * 8;
+    if num_bits == 0 {
+        return;
+    }
+    rotate_left(bitmap, num_bits - n % num_bits);
+}
+
+/// Test a bit of a bitmap whose length is a power of two, wrapping the index
+/// around the length of the bitmap
+///
+/// Masking the index lets the optimizer prove that the access is in bounds,
+/// eliding the bounds check. The bitmap length is only checked to be a power
+/// of two in debug builds, otherwise the index may be wrapped incorrectly.
+#[inline]
+pub fn bit_test_pow2(bitmap: &[u8], idx: usize) -> bool {
+    debug_assert!(bitmap.len().is_power_of_two());
+    let idx = idx & (bitmap.len() * 8 - 1);
+    bitmap[idx >> 3] & (1 << (idx & 7)) != 0
+}
+
+/// Like [`bit_test_pow2()`], but sets the bit
+#[inline]
+pub fn bit_set_pow2(bitmap: &mut [u8], idx: usize) {
+    debug_assert!(bitmap.len().is_power_of_two());
+    let idx = idx & (bitmap.len() * 8 - 1);
+    bitmap[idx >> 3] |= 1 << (idx & 7)
+}
+
+/// Like [`bit_test_pow2()`], but clears the bit
+#[inline]
+pub fn bit_clear_pow2(bitmap: &mut [u8], idx: usize) {
+    debug_assert!(bitmap.len().is_power_of_two());
+    let idx = idx & (bitmap.len() * 8 - 1);
+    bitmap[idx >> 3] &= !(1 << (idx & 7))
+}
+
+/// Test a bit of a bitmap which only stores 6 bits per byte, in the low-order
+/// bits of each byte
+///
+/// Splitting the index requires a true division and remainder by 6, which
+/// shows how much non-power-of-two packing costs with respect to the shift and
+/// mask of [`bit_test_shift_naive()`].
+///
+/// ```
+/// # use bit_test::{bit_set_base6, bit_test_base6};
+/// let mut bitmap = [0u8; 2];
+/// bit_set_base6(&mut bitmap, 7);
+/// assert_eq!(bitmap, [0, 0b10]);
+/// assert!(bit_test_base6(&bitmap, 7));
+/// ```
+#[inline]
+pub fn bit_test_base6(bitmap: &[u8], idx: usize) -> bool {
+    bitmap[idx / 6] & (1 << (idx % 6)) != 0
+}
+
+#[inline]
+pub fn bit_set_base6(bitmap: &mut [u8], idx: usize) {
+    bitmap[idx / 6] |= 1 << (idx % 6)
+}
+
+#[inline]
+pub fn bit_clear_base6(bitmap: &mut [u8], idx: usize) {
+    bitmap[idx / 6] &= !(1 << (idx % 6))
+}
+
+/// Test a bit, while prefetching the byte which contains bit `next_idx` into
+/// the cache so that a subsequent access to it does not stall on memory
+///
+/// `next_idx` does not need to be in bounds, as prefetches never fault.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+pub fn bit_test_prefetch(bitmap: &[u8], idx: usize, next_idx: usize) -> bool {
+    use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+    let next_ptr = bitmap.as_ptr().wrapping_add(next_idx >> 3);
+    // SAFETY: Prefetching is only a hint, which never faults, so any address
+    //         can be prefetched
+    unsafe { _mm_prefetch::<_MM_HINT_T0>(next_ptr.cast::<i8>()) };
+    bit_test_const_table(bitmap, idx)
+}
+
+/// Set the first clear bit of a bitmap and return its index, or return `None`
+/// if all bits are already set
+///
+/// This is the basic operation of a bitmap-based slot allocator.
+///
+/// ```
+/// # use bit_test::alloc_first_clear;
+/// let mut bitmap = [0b1011_1111, 0xff];
+/// assert_eq!(alloc_first_clear(&mut bitmap), Some(6));
+/// assert_eq!(alloc_first_clear(&mut bitmap), None);
+/// ```
+#[inline]
+pub fn alloc_first_clear(bitmap: &mut [u8]) -> Option<usize> {
+    let idx = find_first_clear(bitmap)?;
+    bit_set_const_table(bitmap, idx);
+    Some(idx)
+}
+
+/// Clear the `count` bits starting at `start`, which must all be set
+///
+/// This is the counterpart of [`alloc_first_clear()`] for allocations that
+/// span multiple slots. Freeing slots that are not allocated is a bug, which
+/// is caught in debug builds.
+///
+/// ```
+/// # use bit_test::free_range;
+/// let mut bitmap = [0b0011_1100];
+/// free_range(&mut bitmap, 2, 3);
+/// assert_eq!(bitmap, [0b0010_0000]);
+/// if cfg!(debug_assertions) {
+///     let double_free = std::panic::catch_unwind(move || free_range(&mut bitmap, 2, 3));
+///     assert!(double_free.is_err());
+/// }
+/// ```
+#[inline]
+pub fn free_range(bitmap: &mut [u8], start: usize, count: usize) {
+    let end = start + count;
+    debug_assert!(
+        test_range_all(bitmap, start, end),
+        "attempted to free bits {start}..{end}, which are not all set"
+    );
+    clear_range(bitmap, start, end)
+}
+
+/// Compare two versions of a bitmap, returning iterators over the indices of
+/// the bits which were set and cleared between `old` and `new`, respectively
+///
+/// Behavior is only defined for inputs of equal length, which is checked in
+/// debug builds.
+///
+/// ```
+/// # use bit_test::diff_set_bits;
+/// let old = [0b0000_0001, 0b1000_0000, 0xff];
+/// let new = [0b1000_0001, 0b0000_0001, 0xff];
+/// let (newly_set, newly_cleared) = diff_set_bits(&old, &new);
+/// assert_eq!(newly_set.collect::<Vec<_>>(), [7, 8]);
+/// assert_eq!(newly_cleared.collect::<Vec<_>>(), [15]);
+/// ```
+#[inline]
+pub fn diff_set_bits<'a>(old: &'a [u8], new: &'a [u8]) -> (ChangedBits<'a>, ChangedBits<'a>) {
+    debug_assert_eq!(old.len(), new.len(), "bitmaps should have equal length");
+    (ChangedBits::new(new, old), ChangedBits::new(old, new))
+}
+
+/// Iterator over the indices of the bits which are set in one bitmap but not
+/// in another, in ascending order
+#[derive(Clone, Debug)]
+pub struct ChangedBits<'a> {
+    set: &'a [u8],
+    unset: &'a [u8],
+
+    /// Index of the next byte to be scanned
+    next_byte: usize,
+
+    /// Changed bits of the previous byte that remain to be yielded
+    pending: u8,
+}
+
+impl<'a> ChangedBits<'a> {
+    fn new(set: &'a [u8], unset: &'a [u8]) -> Self {
+        Self {
+            set,
+            unset,
+            next_byte: 0,
+            pending: 0,
+        }
+    }
+}
+
+impl Iterator for ChangedBits<'_> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        while self.pending == 0 {
+            let set = *self.set.get(self.next_byte)?;
+            self.pending = set & !self.unset[self.next_byte];
+            self.next_byte += 1;
+        }
+        let bit = self.pending.trailing_zeros() as usize;
+        self.pending &= self.pending - 1;
+        Some((self.next_byte - 1) * 8 + bit)
+    }
+}
+
+impl core::iter::FusedIterator for ChangedBits<'_> {}
+
+/// Like [`bit_test_const_table()`], but accepts any integer type as an index
+///
+/// This spares callers which use `u32` or `u16` indices from casting them.
+/// Indices which don't fit in `usize`, as can happen with `u32` indices on
+/// 16-bit targets, result in a panic rather than silent truncation.
+///
+/// ```
+/// # use bit_test::{bit_set_idx, bit_test_idx};
+/// let mut bitmap = [0; 4];
+/// let idx: u32 = 17;
+/// bit_set_idx(&mut bitmap, idx);
+/// assert!(bit_test_idx(&bitmap, idx));
+/// assert_eq!(bitmap, [0, 0, 0b10, 0]);
+/// ```
+#[inline]
+pub fn bit_test_idx<I: TryInto<usize>>(bitmap: &[u8], idx: I) -> bool {
+    bit_test_const_table(bitmap, index_to_usize(idx))
+}
+
+#[inline]
+pub fn bit_set_idx<I: TryInto<usize>>(bitmap: &mut [u8], idx: I) {
+    bit_set_const_table(bitmap, index_to_usize(idx))
+}
+
+#[inline]
+pub fn bit_clear_idx<I: TryInto<usize>>(bitmap: &mut [u8], idx: I) {
+    bit_clear_const_table(bitmap, index_to_usize(idx))
+}
+
+#[inline]
+fn index_to_usize<I: TryInto<usize>>(idx: I) -> usize {
+    match idx.try_into() {
+        Ok(idx) => idx,
+        Err(_) => panic!("bit index does not fit in usize"),
+    }
+}
+
+/// Number of bytes of a cache line, for the purpose of [`summarize_lines()`]
+pub const LINE_BYTES: usize = 64;
+
+/// Summarize a bitmap at cache line granularity, producing a bitmap where bit
+/// `i` is set if any bit of the `i`-th 64-byte line of the input is set
+///
+/// A trailing partial line is summarized like a full line. Searching the
+/// summary first lets searches in sparse bitmaps skip empty lines.
+///
+/// ```
+/// # use bit_test::summarize_lines;
+/// let mut bitmap = [0; 1024];
+/// bitmap[3 * 64 + 17] = 0b100;
+/// assert_eq!(summarize_lines(&bitmap), [0b1000, 0]);
+/// ```
+#[cfg(feature = "std")]
+pub fn summarize_lines(bitmap: &[u8]) -> Vec<u8> {
+    let num_lines = bitmap.len().div_ceil(LINE_BYTES);
+    let mut summary = vec![0; num_lines.div_ceil(8)];
+    for (line_idx, line) in bitmap.chunks(LINE_BYTES).enumerate() {
+        if line.iter().any(|&byte| byte != 0) {
+            bit_set_const_table(&mut summary, line_idx);
+        }
+    }
+    summary
+}
+
+/// Bitmap with a second level which tracks which of its cache lines contain
+/// set bits, as computed by [`summarize_lines()`]
+///
+/// This speeds up searches in sparse bitmaps, at the expense of extra work
+/// when bits are set and cleared.
+///
+/// ```
+/// # use bit_test::{summarize_lines, HierBitmap};
+/// let mut bitmap = HierBitmap::with_bytes(1000);
+/// let mut rng_state = 42u32;
+/// for _ in 0..10_000 {
+///     rng_state = rng_state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+///     let idx = (rng_state >> 8) as usize % bitmap.len();
+///     if rng_state >> 31 == 0 {
+///         bitmap.set(idx);
+///     } else {
+///         bitmap.clear(idx);
+///     }
+///     assert_eq!(bitmap.summary(), summarize_lines(bitmap.as_bytes()));
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct HierBitmap {
+    data: Vec<u8>,
+    summary: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl HierBitmap {
+    /// Create a bitmap of `num_bytes * 8` bits, all initially clear
+    pub fn with_bytes(num_bytes: usize) -> Self {
+        Self {
+            data: vec![0; num_bytes],
+            summary: vec![0; num_bytes.div_ceil(LINE_BYTES).div_ceil(8)],
+        }
+    }
+
+    /// Number of bits in the bitmap
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len() * 8
+    }
+
+    /// Truth that the bitmap contains no bits
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Bytes of the bitmap
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Summary of the bitmap, where bit `i` is set if any bit of the `i`-th
+    /// cache line of the bitmap is set
+    #[inline]
+    pub fn summary(&self) -> &[u8] {
+        &self.summary
+    }
+
+    #[inline]
+    pub fn test(&self, idx: usize) -> bool {
+        bit_test_const_table(&self.data, idx)
+    }
+
+    #[inline]
+    pub fn set(&mut self, idx: usize) {
+        bit_set_const_table(&mut self.data, idx);
+        bit_set_const_table(&mut self.summary, idx / (LINE_BYTES * 8));
+    }
+
+    #[inline]
+    pub fn clear(&mut self, idx: usize) {
+        bit_clear_const_table(&mut self.data, idx);
+        // The line's summary bit may only be cleared once all of its bits are
+        // clear, which requires scanning the line
+        let line_idx = idx / (LINE_BYTES * 8);
+        if self.line(line_idx).iter().all(|&byte| byte == 0) {
+            bit_clear_const_table(&mut self.summary, line_idx);
+        }
+    }
+
+    /// Find the first set bit by looking for the first non-empty line in the
+    /// summary, then only searching that line
+    #[inline]
+    pub fn find_first_set(&self) -> Option<usize> {
+        let line_idx = find_first_set(&self.summary)?;
+        let line_bit = find_first_set(self.line(line_idx))
+            .expect("lines marked as non-empty in the summary should have a set bit");
+        Some(line_idx * LINE_BYTES * 8 + line_bit)
+    }
+
+    /// Bytes of the `line_idx`-th cache line of the bitmap
+    fn line(&self, line_idx: usize) -> &[u8] {
+        let start = line_idx * LINE_BYTES;
+        let end = (start + LINE_BYTES).min(self.data.len());
+        &self.data[start..end]
+    }
+}
+
+/// Find the first run of `count` consecutive clear bits of a bitmap, set them
+/// and return the index of the first one, or return `None` if there is no
+/// such run
+///
+/// This is the counterpart of [`alloc_first_clear()`] for allocations that
+/// span multiple slots, which can be freed using [`free_range()`].
+///
+/// ```
+/// # use bit_test::alloc_run;
+/// let mut bitmap = [0b0011_1111, 0b1111_0000];
+/// assert_eq!(alloc_run(&mut bitmap, 6), Some(6));
+/// assert_eq!(bitmap, [0xff, 0xff]);
+///
+/// let mut bitmap = [0b0000_0001, 0b0100_0000];
+/// assert_eq!(alloc_run(&mut bitmap, 14), None);
+/// assert_eq!(alloc_run(&mut bitmap, 13), Some(1));
+/// assert_eq!(alloc_run(&mut bitmap, 1), Some(15));
+/// assert_eq!(bitmap, [0xff, 0xff]);
+/// ```
+#[inline]
+pub fn alloc_run(bitmap: &mut [u8], count: usize) -> Option<usize> {
+    let start = find_clear_run(bitmap, count)?;
+    set_range(bitmap, start, start + count);
+    Some(start)
+}
+
+/// Find the first run of `count` consecutive clear bits of a bitmap
+#[inline]
+fn find_clear_run(bitmap: &[u8], count: usize) -> Option<usize> {
+    if count == 0 {
+        return Some(0);
+    }
+    let mut run_start = 0;
+    let mut run_len = 0;
+    for (byte_idx, &byte) in bitmap.iter().enumerate() {
+        match byte {
+            0 => run_len += 8,
+            u8::MAX => {
+                run_start = (byte_idx + 1) * 8;
+                run_len = 0;
+            }
+            _ => {
+                for bit in 0..8 {
+                    if byte & (1 << bit) == 0 {
+                        run_len += 1;
+                        if run_len == count {
+                            return Some(run_start);
+                        }
+                    } else {
+                        run_start = byte_idx * 8 + bit + 1;
+                        run_len = 0;
+                    }
+                }
+            }
+        }
+        if run_len >= count {
+            return Some(run_start);
+        }
+    }
+    None
+}
+
+/// Clear bit `from` and set bit `to`, in a single read-modify-write operation
+/// if both bits belong to the same byte
+///
+/// If `from` and `to` are equal, the bit ends up set.
+///
+/// ```
+/// # use bit_test::move_bit;
+/// let mut bitmap = [0b0000_0001, 0];
+/// move_bit(&mut bitmap, 0, 3);
+/// assert_eq!(bitmap, [0b0000_1000, 0]);
+/// move_bit(&mut bitmap, 3, 12);
+/// assert_eq!(bitmap, [0, 0b0001_0000]);
+/// move_bit(&mut bitmap, 12, 12);
+/// assert_eq!(bitmap, [0, 0b0001_0000]);
+/// ```
+#[inline]
+pub fn move_bit(bitmap: &mut [u8], from: usize, to: usize) {
+    let (from_byte, to_byte) = (from >> 3, to >> 3);
+    if from_byte == to_byte {
+        let byte = &mut bitmap[from_byte];
+        *byte = (*byte & UNSET_BIT_MASK[from & 7]) | BIT_MASK[to & 7];
+    } else {
+        bit_clear_const_table(bitmap, from);
+        bit_set_const_table(bitmap, to);
+    }
+}
+
+// Smoke test which is only built when the `std` feature is disabled, to check
+// that the slice-based API remains available in no_std builds. Coercing the
+// function to a pointer makes it type-check without ever being called.
+#[cfg(not(feature = "std"))]
+const _: fn(&mut [u8]) -> (usize, Option<usize>) = |bitmap| {
+    bit_set_naive(bitmap, 0);
+    bit_toggle_const_table(bitmap, 1);
+    bit_clear_static_table(bitmap, 2);
+    set_range(bitmap, 3, 12);
+    let _ = bit_set_checked(bitmap, 13);
+    let _ = bit_test_checked(bitmap, 14);
+    (count_ones(bitmap), find_first_set(bitmap))
+};
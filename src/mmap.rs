@@ -0,0 +1,52 @@
+//! Read-only access to bitmaps that are stored in files
+//!
+//! The file is mapped into memory instead of being read, so the bitmap is
+//! brought into RAM page by page as it is accessed, and pages which are not
+//! accessed are never loaded. Writes are not supported: the mapping is
+//! read-only, and modifying the file while it is mapped is undefined behavior.
+
+use core::ops::Deref;
+use memmap2::Mmap;
+use std::{fs::File, io, path::Path};
+
+/// Bitmap which is backed by a read-only memory-mapped file
+///
+/// Derefs to `[u8]`, so all read-only operations of this crate can be used on
+/// it directly.
+///
+/// ```
+/// # use bit_test::{bit_test_naive, count_ones, find_first_set, mmap::open_mmap};
+/// let path = std::env::temp_dir().join("bit_test_mmap_doctest.bin");
+/// std::fs::write(&path, [0b0000_0000, 0b0010_0100])?;
+/// let bitmap = open_mmap(&path)?;
+/// assert!(bit_test_naive(&bitmap, 10));
+/// assert_eq!(count_ones(&bitmap), 2);
+/// assert_eq!(find_first_set(&bitmap), Some(10));
+/// # drop(bitmap);
+/// # std::fs::remove_file(&path)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct MappedBitmap(Mmap);
+
+impl Deref for MappedBitmap {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Map the file at `path` into memory as a read-only bitmap
+///
+/// The file must not be modified, truncated or deleted while the resulting
+/// [`MappedBitmap`] is alive, otherwise reading it is undefined behavior (or
+/// a SIGBUS on truncation).
+pub fn open_mmap(path: impl AsRef<Path>) -> io::Result<MappedBitmap> {
+    let file = File::open(path)?;
+    // SAFETY: The mapping is read-only, and the caller is responsible for not
+    //         modifying the file while it is mapped, as documented above.
+    let map = unsafe { Mmap::map(&file)? };
+    Ok(MappedBitmap(map))
+}
@@ -0,0 +1,26 @@
+//! Copies of some bit operations which are never inlined
+//!
+//! All other functions of this crate are `#[inline]`. Benchmarking them
+//! against these copies measures the cost of a function call, and of the
+//! optimizations that are lost when the caller can't see the callee's code.
+
+/// Generate a non-inlined wrapper around a function of the crate root
+macro_rules! noinline {
+    ($($name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)?;)*) => {
+        $(
+            #[doc = concat!("Like [`", stringify!($name), "()`](crate::", stringify!($name), "), but never inlined")]
+            #[inline(never)]
+            pub fn $name($($arg: $ty),*) $(-> $ret)? {
+                crate::$name($($arg),*)
+            }
+        )*
+    };
+}
+noinline! {
+    bit_test_naive(bitmap: &[u8], idx: usize) -> bool;
+    bit_test_const_table(bitmap: &[u8], idx: usize) -> bool;
+    bit_set_naive(bitmap: &mut [u8], idx: usize);
+    bit_set_const_table(bitmap: &mut [u8], idx: usize);
+    bit_clear_naive(bitmap: &mut [u8], idx: usize);
+    bit_clear_const_table(bitmap: &mut [u8], idx: usize);
+}
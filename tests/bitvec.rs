@@ -0,0 +1,36 @@
+//! Check that bitmaps survive a round trip through the bit vectors of the
+//! `bitvec` crate with the same bit indices
+
+#![cfg(feature = "bitvec")]
+
+use bit_test::{
+    bit_test_naive,
+    bitvec::{from_bitvec, to_bitvec},
+    Bitmap,
+};
+use common::{pattern, BITS};
+
+mod common;
+
+#[test]
+fn bitvec_round_trip() {
+    let bytes = pattern();
+    for bit_len in [0, 1, 7, 8, 9, BITS - 1, BITS] {
+        let bits = to_bitvec(&bytes, bit_len);
+        for idx in 0..bit_len {
+            assert_eq!(
+                bits[idx],
+                bit_test_naive(&bytes, idx),
+                "bitvec disagrees with bit_test_naive on bit {idx}"
+            );
+        }
+        let (round_trip, round_trip_len) = from_bitvec(&bits);
+        assert_eq!(round_trip_len, bit_len);
+        let expected = Bitmap::from_iter((0..bit_len).map(|idx| bit_test_naive(&bytes, idx)));
+        assert_eq!(
+            round_trip,
+            expected.as_bytes(),
+            "round trip of {bit_len} bits"
+        );
+    }
+}
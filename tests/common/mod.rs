@@ -0,0 +1,18 @@
+//! Inputs shared by the integration tests
+
+/// Number of bits of the bitmaps, which is a power of two for the sake of the
+/// `_pow2` variants
+pub const BITS: usize = 256;
+
+/// Bitmap whose bits look random, but are the same on every run
+pub fn pattern() -> Vec<u8> {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    (0..BITS / 8)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
@@ -0,0 +1,455 @@
+//! Check that all implementations of each bit operation agree with the naive
+//! implementation, on every bit of a pseudo-random bitmap
+//!
+//! Implementations which use a different storage word or bit numbering are
+//! given a bitmap that is converted to their representation, and their output
+//! is converted back to LSB-first bytes before comparison.
+
+use bit_test::*;
+use common::{pattern, BITS};
+
+mod common;
+
+/// Storage word which can be converted to and from little-endian bytes
+trait Word: Copy {
+    fn from_le(bytes: &[u8]) -> Self;
+    fn to_le(self) -> Vec<u8>;
+}
+
+macro_rules! impl_word {
+    ($($word:ty),*) => {
+        $(
+            impl Word for $word {
+                fn from_le(bytes: &[u8]) -> Self {
+                    Self::from_le_bytes(bytes.try_into().unwrap())
+                }
+
+                fn to_le(self) -> Vec<u8> {
+                    self.to_le_bytes().to_vec()
+                }
+            }
+        )*
+    };
+}
+impl_word!(u8, u16, u32, u64, usize);
+
+/// Convert LSB-first bytes into words where bit `i` has the same index
+fn to_words<W: Word>(bytes: &[u8]) -> Vec<W> {
+    bytes
+        .chunks_exact(core::mem::size_of::<W>())
+        .map(W::from_le)
+        .collect()
+}
+
+/// Inverse of [`to_words()`]
+fn from_words<W: Word>(words: &[W]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le()).collect()
+}
+
+/// Convert between LSB-first and MSB-first bit numbering, in either direction
+fn flip_bit_order(bytes: &[u8]) -> Vec<u8> {
+    let mut bytes = bytes.to_vec();
+    reverse_byte_bits(&mut bytes);
+    bytes
+}
+
+/// Check that `test` agrees with [`bit_test_naive()`] on every bit
+fn check_test<W>(name: &str, convert: fn(&[u8]) -> Vec<W>, test: impl Fn(&[W], usize) -> bool) {
+    let bytes = pattern();
+    let words = convert(&bytes);
+    for idx in 0..BITS {
+        assert_eq!(
+            test(&words, idx),
+            bit_test_naive(&bytes, idx),
+            "{name} disagrees with bit_test_naive on bit {idx}"
+        );
+    }
+}
+
+/// Check that applying `change` to every bit, one at a time, has the same
+/// effect on the bitmap and returns the same value as `reference`
+fn check_change<W, R: PartialEq + core::fmt::Debug>(
+    name: &str,
+    (convert, convert_back): Repr<W>,
+    change: impl Fn(&mut [W], usize) -> R,
+    reference: fn(&mut [u8], usize) -> R,
+) {
+    for idx in 0..BITS {
+        let mut expected = pattern();
+        let expected_result = reference(&mut expected, idx);
+        let mut words = convert(&pattern());
+        let result = change(&mut words, idx);
+        assert_eq!(
+            result, expected_result,
+            "{name} returned a bad result for bit {idx}"
+        );
+        assert_eq!(
+            convert_back(&words),
+            expected,
+            "{name} disagrees with the naive version on bit {idx}"
+        );
+    }
+}
+
+/// Conversions from LSB-first bytes to some storage representation, and back
+type Repr<W> = (fn(&[u8]) -> Vec<W>, fn(&[W]) -> Vec<u8>);
+
+const BYTES: Repr<u8> = (to_words::<u8>, from_words::<u8>);
+const MSB_FIRST: Repr<u8> = (flip_bit_order, flip_bit_order);
+const U16: Repr<u16> = (to_words::<u16>, from_words::<u16>);
+const U32: Repr<u32> = (to_words::<u32>, from_words::<u32>);
+const U64: Repr<u64> = (to_words::<u64>, from_words::<u64>);
+const USIZE: Repr<usize> = (to_words::<usize>, from_words::<usize>);
+
+macro_rules! check_tests {
+    ($($repr:ident => [$($op:path),*]),* $(,)?) => {
+        $($(
+            check_test(stringify!($op), $repr.0, |words, idx| $op(words, idx));
+        )*)*
+    };
+}
+
+macro_rules! check_changes {
+    ($reference:path; $($repr:ident => [$($op:path),*]),* $(,)?) => {
+        $($(
+            check_change(stringify!($op), $repr, |words, idx| $op(words, idx), $reference);
+        )*)*
+    };
+}
+
+#[test]
+fn bit_test_variants() {
+    check_tests!(
+        BYTES => [
+            bit_test_naive,
+            bit_test_shift_naive,
+            bit_test_const_table,
+            bit_test_static_table,
+            bit_test_boxed_table,
+            bit_test_const_fn,
+            bit_test_pow2,
+            bit_test_generic
+        ],
+        MSB_FIRST => [bit_test_msb, bit_test_msb_table],
+        U16 => [bit_test_generic],
+        U32 => [bit_test_generic],
+        U64 => [bit_test_u64, bit_test_generic],
+        USIZE => [bit_test_word, bit_test_generic],
+    );
+    check_test("bit_test_checked", BYTES.0, |bytes, idx| {
+        bit_test_checked(bytes, idx).unwrap()
+    });
+    check_test("bit_test_idx", BYTES.0, |bytes, idx| {
+        bit_test_idx(bytes, idx as u32)
+    });
+    check_test("bit_get_u8", BYTES.0, |bytes, idx| {
+        bit_get_u8(bytes, idx) == 1
+    });
+    check_test("bit_test_byte", BYTES.0, |bytes, idx| {
+        bit_test_byte(bytes, idx).0
+    });
+    // SAFETY: All indices are within the bitmap
+    check_test("bit_test_unchecked", BYTES.0, |bytes, idx| unsafe {
+        bit_test_unchecked(bytes, idx)
+    });
+    // SAFETY: All indices are within the bitmap
+    check_test("bit_test_ptr", BYTES.0, |bytes, idx| unsafe {
+        bit_test_ptr(bytes.as_ptr(), idx)
+    });
+    #[cfg(target_arch = "x86_64")]
+    check_test("bit_test_prefetch", BYTES.0, |bytes, idx| {
+        bit_test_prefetch(bytes, idx, idx + 1)
+    });
+}
+
+#[test]
+fn bit_set_variants() {
+    check_changes!(
+        bit_set_naive;
+        BYTES => [
+            bit_set_naive,
+            bit_set_const_table,
+            bit_set_static_table,
+            bit_set_pow2,
+            bit_set_generic
+        ],
+        MSB_FIRST => [bit_set_msb, bit_set_msb_table],
+        U16 => [bit_set_generic],
+        U32 => [bit_set_generic],
+        U64 => [bit_set_u64, bit_set_generic],
+        USIZE => [bit_set_word, bit_set_generic],
+    );
+    check_change(
+        "bit_set_checked",
+        BYTES,
+        |bytes, idx| bit_set_checked(bytes, idx).unwrap(),
+        bit_set_naive,
+    );
+    check_change(
+        "bit_set_idx",
+        BYTES,
+        |bytes, idx| bit_set_idx(bytes, idx as u32),
+        bit_set_naive,
+    );
+    // SAFETY: All indices are within the bitmap
+    check_change(
+        "bit_set_ptr",
+        BYTES,
+        |bytes, idx| unsafe { bit_set_ptr(bytes.as_mut_ptr(), idx) },
+        bit_set_naive,
+    );
+}
+
+#[test]
+fn bit_clear_variants() {
+    check_changes!(
+        bit_clear_naive;
+        BYTES => [
+            bit_clear_naive,
+            bit_clear_const_table,
+            bit_clear_static_table,
+            bit_clear_pow2,
+            bit_clear_generic
+        ],
+        MSB_FIRST => [bit_clear_msb, bit_clear_msb_table],
+        U16 => [bit_clear_generic],
+        U32 => [bit_clear_generic],
+        U64 => [bit_clear_u64, bit_clear_generic],
+        USIZE => [bit_clear_word, bit_clear_generic],
+    );
+    check_change(
+        "bit_clear_checked",
+        BYTES,
+        |bytes, idx| bit_clear_checked(bytes, idx).unwrap(),
+        bit_clear_naive,
+    );
+    check_change(
+        "bit_clear_idx",
+        BYTES,
+        |bytes, idx| bit_clear_idx(bytes, idx as u32),
+        bit_clear_naive,
+    );
+    // SAFETY: All indices are within the bitmap
+    check_change(
+        "bit_clear_ptr",
+        BYTES,
+        |bytes, idx| unsafe { bit_clear_ptr(bytes.as_mut_ptr(), idx) },
+        bit_clear_naive,
+    );
+}
+
+#[test]
+fn bit_toggle_variants() {
+    check_changes!(
+        bit_toggle_naive;
+        BYTES => [bit_toggle_naive, bit_toggle_const_table, bit_toggle_static_table],
+    );
+}
+
+#[test]
+fn bit_test_and_change_variants() {
+    check_changes!(
+        bit_test_and_set_naive;
+        BYTES => [
+            bit_test_and_set_naive,
+            bit_test_and_set_const_table,
+            bit_test_and_set_static_table
+        ],
+    );
+    check_changes!(
+        bit_test_and_clear_naive;
+        BYTES => [
+            bit_test_and_clear_naive,
+            bit_test_and_clear_const_table,
+            bit_test_and_clear_static_table
+        ],
+    );
+    check_change("try_set", BYTES, try_set, |bytes, idx| {
+        !bit_test_and_set_naive(bytes, idx)
+    });
+    check_change("try_clear", BYTES, try_clear, bit_test_and_clear_naive);
+}
+
+#[test]
+fn bit_assign_variants() {
+    for value in [false, true] {
+        let reference = if value {
+            bit_set_naive
+        } else {
+            bit_clear_naive
+        };
+        check_change(
+            "bit_assign_branching",
+            BYTES,
+            |bytes, idx| bit_assign_branching(bytes, idx, value),
+            reference,
+        );
+        check_change(
+            "bit_assign_naive",
+            BYTES,
+            |bytes, idx| bit_assign_naive(bytes, idx, value),
+            reference,
+        );
+        check_change(
+            "bit_assign_const_table",
+            BYTES,
+            |bytes, idx| bit_assign_const_table(bytes, idx, value),
+            reference,
+        );
+        check_change(
+            "bit_assign_static_table",
+            BYTES,
+            |bytes, idx| bit_assign_static_table(bytes, idx, value),
+            reference,
+        );
+    }
+}
+
+#[test]
+fn read_u16_at_variants() {
+    let bytes = pattern();
+    for bit_offset in 0..=BITS - 16 {
+        assert_eq!(
+            u64::from(read_u16_at(&bytes, bit_offset)),
+            get_bits(&bytes, bit_offset, 16),
+            "read_u16_at disagrees with get_bits at offset {bit_offset}"
+        );
+    }
+}
+
+#[test]
+fn range_checked_variants() {
+    let bytes = pattern();
+    for start in 0..=BITS {
+        for end in start..=BITS {
+            let (mut expected, mut actual) = (bytes.clone(), bytes.clone());
+            set_range(&mut expected, start, end);
+            assert_eq!(set_range_checked(&mut actual, start, end), Ok(()));
+            assert_eq!(actual, expected, "set_range_checked on {start}..{end}");
+            clear_range(&mut expected, start, end);
+            assert_eq!(clear_range_checked(&mut actual, start, end), Ok(()));
+            assert_eq!(actual, expected, "clear_range_checked on {start}..{end}");
+            assert_eq!(
+                test_range_all_checked(&bytes, start, end),
+                Ok(test_range_all(&bytes, start, end)),
+                "test_range_all_checked on {start}..{end}"
+            );
+        }
+    }
+}
+
+#[test]
+fn count_ones_in_range_variants() {
+    let bytes = pattern();
+    // Every range of the bitmap, including empty ones and ones that start and
+    // end inside of the same byte
+    for start in 0..=BITS {
+        for end in start..=BITS {
+            let expected = (start..end)
+                .filter(|&idx| bit_test_naive(&bytes, idx))
+                .count();
+            assert_eq!(
+                count_ones_in_range(&bytes, start, end),
+                expected,
+                "count_ones_in_range disagrees with bit_test_naive on {start}..{end}"
+            );
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn bucket_popcounts_variants() {
+    let bytes = pattern();
+    for bucket_bits in 1..=BITS + 1 {
+        let expected = (0..BITS)
+            .step_by(bucket_bits)
+            .map(|start| {
+                let end = (start + bucket_bits).min(BITS);
+                (start..end)
+                    .filter(|&idx| bit_test_naive(&bytes, idx))
+                    .count() as u32
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            bucket_popcounts(&bytes, bucket_bits),
+            expected,
+            "bucket_popcounts disagrees with bit_test_naive for {bucket_bits}-bit buckets"
+        );
+    }
+}
+
+#[test]
+fn copy_bits_variants() {
+    let src = pattern();
+    let original_dst = src.iter().rev().map(|&byte| !byte).collect::<Vec<_>>();
+    for src_shift in 0..8 {
+        for dst_shift in 0..8 {
+            for count in [0, 1, 7, 8, 9, 63, 64, 65, 130] {
+                let (src_start, dst_start) = (8 + src_shift, 16 + dst_shift);
+                let mut expected = original_dst.clone();
+                for i in 0..count {
+                    let bit = bit_test_naive(&src, src_start + i);
+                    bit_assign_naive(&mut expected, dst_start + i, bit);
+                }
+                let mut dst = original_dst.clone();
+                copy_bits(&mut dst, dst_start, &src, src_start, count);
+                assert_eq!(
+                    dst, expected,
+                    "copy_bits of {count} bits from bit {src_start} to bit {dst_start}"
+                );
+            }
+        }
+    }
+}
+
+/// Check that a vectorized bit count agrees with [`count_ones()`], on inputs
+/// which end with a partial vector, and on inputs of all-ones bytes which are
+/// long enough to overflow per-lane accumulators if they weren't flushed
+#[cfg(any(
+    feature = "portable_simd",
+    all(target_arch = "aarch64", target_feature = "neon")
+))]
+fn check_count_ones(name: &str, count: fn(&[u8]) -> usize) {
+    for len in [0, 1, 15, 16, 17, 31, 32, 33, 64 * 32 + 5] {
+        let random = pattern().into_iter().cycle().take(len).collect::<Vec<_>>();
+        let ones = vec![u8::MAX; len];
+        for bytes in [random, ones] {
+            assert_eq!(
+                count(&bytes),
+                count_ones(&bytes),
+                "{name} disagrees with count_ones on {len} bytes"
+            );
+        }
+    }
+}
+
+#[cfg(feature = "portable_simd")]
+#[test]
+fn count_ones_simd_variants() {
+    check_count_ones("count_ones_simd", count_ones_simd);
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+#[test]
+fn count_ones_neon_variants() {
+    check_count_ones("count_ones_neon", count_ones_neon);
+}
+
+#[cfg(feature = "portable_simd")]
+#[test]
+fn find_first_set_simd_variants() {
+    for len in [0, 1, 31, 32, 33, 95, 4 * 32] {
+        let zeros = vec![0; len];
+        assert_eq!(find_first_set_simd(&zeros), None, "{len} zero bytes");
+        for idx in 0..len * 8 {
+            let mut bytes = zeros.clone();
+            bit_set_naive(&mut bytes, idx);
+            assert_eq!(
+                find_first_set_simd(&bytes),
+                find_first_set(&bytes),
+                "find_first_set_simd disagrees with find_first_set on bit {idx} of {len} bytes"
+            );
+        }
+    }
+}
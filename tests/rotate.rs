@@ -0,0 +1,74 @@
+//! Check the bit rotations of slices and of [`Bitmap`] against a bit-by-bit
+//! rotation, for rotation amounts that are and aren't multiples of 8
+
+use bit_test::*;
+use common::{pattern, BITS};
+
+mod common;
+
+/// Rotate the first `bit_len` bits of `bytes` towards higher indices, one bit
+/// at a time
+fn rotate_left_naive(bytes: &[u8], bit_len: usize, n: usize) -> Vec<bool> {
+    let mut rotated = vec![false; bit_len];
+    for idx in 0..bit_len {
+        rotated[(idx + n) % bit_len] = bit_test_naive(bytes, idx);
+    }
+    rotated
+}
+
+const ROTATIONS: [usize; 9] = [0, 1, 7, 8, 9, 64, 255, 256, 300];
+
+#[test]
+fn rotate_variants() {
+    let bytes = pattern();
+    for n in ROTATIONS {
+        let expected = rotate_left_naive(&bytes, BITS, n);
+        let mut left = bytes.clone();
+        rotate_left(&mut left, n);
+        let mut right = bytes.clone();
+        rotate_right(&mut right, BITS - n % BITS);
+        for (idx, &bit) in expected.iter().enumerate() {
+            assert_eq!(
+                bit_test_naive(&left, idx),
+                bit,
+                "rotate_left by {n}, bit {idx}"
+            );
+            assert_eq!(
+                bit_test_naive(&right, idx),
+                bit,
+                "rotate_right by {}, bit {idx}",
+                BITS - n % BITS
+            );
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn bitmap_rotate_variants() {
+    let bytes = pattern();
+    for bit_len in [0, 1, 13, 64, 203] {
+        for n in ROTATIONS {
+            let bitmap = Bitmap::from_iter((0..bit_len).map(|idx| bit_test_naive(&bytes, idx)));
+            let expected = Bitmap::from_iter(rotate_left_naive(&bytes, bit_len, n));
+            // Bitmap equality compares whole bytes, so this also checks that the
+            // padding bits of the last byte stay clear
+            let mut left = bitmap.clone();
+            left.rotate_left(n);
+            assert_eq!(left, expected, "rotate_left of {bit_len} bits by {n}");
+            // Rotating right by the complement of n is the same as rotating
+            // left by n
+            let right_n = if bit_len == 0 {
+                0
+            } else {
+                bit_len - n % bit_len
+            };
+            let mut right = bitmap.clone();
+            right.rotate_right(right_n);
+            assert_eq!(
+                right, expected,
+                "rotate_right of {bit_len} bits by {right_n}"
+            );
+        }
+    }
+}